@@ -3,3 +3,11 @@ pub const PUBKEY_SIZE: usize = 32;
 pub const VEC_PREFIX_SIZE: usize = 4;
 pub const U32_SIZE: usize = 4;
 pub const TREE_STATUS_SIZE: usize = 1;
+pub const MAX_NAME_LENGTH: usize = 32;
+pub const MAX_URI_LENGTH: usize = 200;
+pub const CONFIG_LINE_SIZE: usize = MAX_NAME_LENGTH + MAX_URI_LENGTH;
+pub const MAX_PAYMENT_MINTS: usize = 5;
+pub const MAX_RECIPIENTS: usize = 5;
+pub const BASIS_POINTS: u16 = 10_000;
+pub const MAX_CURVE_POINTS: usize = 10;
+pub const MAX_GATE_DISCOUNTS: usize = 6;
@@ -20,4 +20,152 @@ pub enum CustomError {
     InvalidSourceRemainingAccount,
     #[msg("Invalid destination remaining accounts")]
     InvalidDestinationRemainingAccount,
+    #[msg("Invalid config line index")]
+    InvalidConfigLineIndex,
+    #[msg("Config line name or uri is too long")]
+    ConfigLineTooLong,
+    #[msg("Invalid config line")]
+    InvalidConfigLine,
+    #[msg("Config lines have not been fully loaded")]
+    ConfigLinesNotLoaded,
+    #[msg("Config lines cannot be changed after minting has started")]
+    ConfigLinesLocked,
+    #[msg("Invalid hidden settings")]
+    InvalidHiddenSettings,
+    #[msg("Hidden settings are not enabled")]
+    HiddenSettingsNotEnabled,
+    #[msg("Invalid allow list proof")]
+    InvalidAllowListProof,
+    #[msg("User Already Allowed")]
+    UserAlreadyAllowed,
+    #[msg("Invalid allow list entry account")]
+    InvalidAllowListEntry,
+    #[msg("Sale has not started")]
+    SaleNotStarted,
+    #[msg("Sale has ended")]
+    SaleEnded,
+    #[msg("Invalid sale window")]
+    InvalidSaleWindow,
+    #[msg("No active phase")]
+    NoActivePhase,
+    #[msg("Phase supply cap reached")]
+    PhaseSoldOut,
+    #[msg("Invalid phases")]
+    InvalidPhases,
+    #[msg("Mint counter account is required")]
+    MintCounterRequired,
+    #[msg("Wallet mint limit reached")]
+    MintLimitReached,
+    #[msg("Gatekeeper signature required")]
+    GatekeeperRequired,
+    #[msg("Invalid gatekeeper signature")]
+    InvalidGatekeeperSignature,
+    #[msg("Gatekeeper signature expired")]
+    GatekeeperSignatureExpired,
+    #[msg("Gatekeeper nonce already used")]
+    GatekeeperNonceUsed,
+    #[msg("Token gate account is required")]
+    TokenGateRequired,
+    #[msg("Invalid token gate account")]
+    InvalidTokenGateAccount,
+    #[msg("Not enough tokens held")]
+    NotEnoughTokens,
+    #[msg("Token gate already claimed")]
+    TokenGateAlreadyClaimed,
+    #[msg("NFT gate accounts are required")]
+    NftGateRequired,
+    #[msg("Invalid NFT gate account")]
+    InvalidNftGateAccount,
+    #[msg("NFT is not part of the gate collection")]
+    InvalidNftCollection,
+    #[msg("NFT already used")]
+    NftGateAlreadyUsed,
+    #[msg("cNFT gate accounts and proof are required")]
+    CnftGateRequired,
+    #[msg("Invalid cNFT gate merkle tree")]
+    InvalidCnftGateTree,
+    #[msg("Invalid cNFT gate proof")]
+    InvalidCnftGateProof,
+    #[msg("cNFT already used")]
+    CnftGateAlreadyUsed,
+    #[msg("Invalid allow mint amount")]
+    InvalidAllowMintAmount,
+    #[msg("SPL payment accounts are required")]
+    PaymentAccountsRequired,
+    #[msg("Invalid payment mint")]
+    InvalidPaymentMint,
+    #[msg("Invalid payment source account")]
+    InvalidPaymentSource,
+    #[msg("Invalid payment destination account")]
+    InvalidPaymentDestination,
+    #[msg("Invalid payment amount")]
+    InvalidPaymentAmount,
+    #[msg("Payment mint has unsupported extensions")]
+    UnsupportedPaymentMint,
+    #[msg("Invalid payment mints")]
+    InvalidPaymentMints,
+    #[msg("Invalid recipients")]
+    InvalidRecipients,
+    #[msg("Invalid recipient account")]
+    InvalidRecipientAccount,
+    #[msg("Invalid treasury account")]
+    InvalidTreasuryAccount,
+    #[msg("Invalid withdraw accounts")]
+    InvalidWithdrawAccounts,
+    #[msg("Invalid Dutch auction")]
+    InvalidDutchAuction,
+    #[msg("Dutch auction with rebates already has sales")]
+    DutchAuctionLocked,
+    #[msg("Auction receipt is required")]
+    AuctionReceiptRequired,
+    #[msg("Dutch auction is not settled")]
+    AuctionNotSettled,
+    #[msg("No rebate to claim")]
+    NoRebate,
+    #[msg("Not enough funds in the treasury")]
+    InsufficientTreasuryFunds,
+    #[msg("Invalid bonding curve")]
+    InvalidBondingCurve,
+    #[msg("Price exceeds the max price")]
+    PriceExceedsMax,
+    #[msg("Invalid discount")]
+    InvalidDiscount,
+    #[msg("Invalid USD price")]
+    InvalidUsdPrice,
+    #[msg("Invalid price oracle account")]
+    InvalidOracle,
+    #[msg("Invalid oracle price")]
+    InvalidOraclePrice,
+    #[msg("Oracle price is stale")]
+    StaleOraclePrice,
+    #[msg("Oracle price confidence is too wide")]
+    OracleConfidenceTooWide,
+    #[msg("Invalid sale goal")]
+    InvalidSaleGoal,
+    #[msg("Sale goal already has sales")]
+    SaleGoalLocked,
+    #[msg("Goal receipt is required")]
+    GoalReceiptRequired,
+    #[msg("Sale goal was not met by the deadline")]
+    SaleGoalFailed,
+    #[msg("Sale goal is not met")]
+    SaleGoalNotMet,
+    #[msg("Sale goal has not failed")]
+    SaleGoalNotFailed,
+    #[msg("Sales with a goal can only be paid in SOL")]
+    SaleGoalSolOnly,
+    #[msg("Collection is sold out")]
+    SoldOut,
+    #[msg("Allow list entry has claimed mints while the merkle allow list is set")]
+    AllowListEntryClaimed,
+    #[msg("Invalid refund leaves")]
+    InvalidRefundLeaves,
+    #[msg("Token gate tokens are locked until the sale is over")]
+    TokenGateLocked,
+    #[msg("Collection is not sold out")]
+    NotSoldOut,
+    #[msg("Sale goal payments are not refunded yet")]
+    RefundsOutstanding,
+    #[msg("Rebates are not claimed yet")]
+    RebatesOutstanding,
 }
\ No newline at end of file
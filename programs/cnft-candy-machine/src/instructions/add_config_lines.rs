@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{
+        Config, 
+        ConfigLine, 
+        ConfigLines
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+#[instruction(index: u32, lines: Vec<ConfigLine>)]
+pub struct AddConfigLines<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"config_lines", config.key().as_ref()],
+        bump = config_lines.bump,
+        realloc = ConfigLines::space((config_lines.line_count as usize).max(index as usize + lines.len())),
+        realloc::payer = authority,
+        realloc::zero = true,
+    )]
+    pub config_lines: Account<'info, ConfigLines>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AddConfigLines<'info> {
+    pub fn add(&mut self, index: u32, lines: Vec<ConfigLine>) -> Result<()> {
+        // Once the first cNFT has been minted, the loaded lines are locked and new lines can only be appended. With hidden settings,
+        // this keeps the lines uploaded for the reveal from being rewritten after they were checked against the provenance hash
+        require!(self.config.current_supply == 0 || index >= self.config_lines.line_count, CustomError::ConfigLinesLocked);
+
+        // Lines can overwrite existing ones or be appended, but cannot leave gaps or exceed the total supply
+        let end = index.checked_add(lines.len() as u32).ok_or(CustomError::InvalidConfigLineIndex)?;
+        require!(index <= self.config_lines.line_count, CustomError::InvalidConfigLineIndex);
+        require!(end <= self.config.total_supply, CustomError::InvalidConfigLineIndex);
+
+        // Write each line into its slot
+        let config_lines = self.config_lines.to_account_info();
+        for (i, line) in lines.iter().enumerate() {
+            ConfigLines::write_line(&config_lines, index + i as u32, line)?;
+        }
+
+        // Update the number of loaded lines
+        self.config_lines.line_count = self.config_lines.line_count.max(end);
+        Ok(())
+    }
+}
@@ -1,35 +1,54 @@
 use anchor_lang::prelude::*;
 
-use crate::state::{
-    AllowListStruct, 
-    Config
+use crate::{
+    constants::BASIS_POINTS, 
+    state::{
+        AllowListEntry, 
+        Config
+    }, 
+    CustomError
 };
 
 #[derive(Accounts)]
+#[instruction(user: Pubkey)]
 pub struct AllowList<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
     #[account(
-        mut,
         seeds = [b"config", authority.key().as_ref()],
         bump = config.bump,
-        realloc = Config::INIT_SPACE + (config.allow_list.len() * AllowListStruct::INIT_SPACE) + AllowListStruct::INIT_SPACE,
-        realloc::payer = authority,
-        realloc::zero = true,
     )]
     pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [b"allow", config.key().as_ref(), user.as_ref()],
+        bump,
+        space = AllowListEntry::INIT_SPACE,
+    )]
+    pub allow_list_entry: Account<'info, AllowListEntry>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> AllowList<'info> {
-    pub fn add(&mut self, user: Pubkey, amount: u8) -> Result<()> {
-        // Add the user to the allow list
-        self.config.allow_list.push(
-            AllowListStruct{
+    pub fn add(&mut self, user: Pubkey, amount: u8, discount_bps: Option<u16>, bumps: &AllowListBumps) -> Result<()> {
+        // Check if the user is already in the allow list
+        require_keys_eq!(self.allow_list_entry.user, Pubkey::default(), CustomError::UserAlreadyAllowed);
+
+        // Check if the discount is not above the full price
+        require!(discount_bps.unwrap_or(0) <= BASIS_POINTS, CustomError::InvalidDiscount);
+
+        // Add the user to the allow list by initializing the user allow list entry
+        self.allow_list_entry.set_inner(
+            AllowListEntry {
                 user,
-                amount,
+                allotted: amount,
+                claimed: 0,
+                discount_bps,
+                merkle: false,
+                bump: bumps.allow_list_entry,
             }
         );
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,118 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{
+    allocate, 
+    assign, 
+    create_account, 
+    transfer, 
+    Allocate, 
+    Assign, 
+    CreateAccount, 
+    Transfer
+};
+
+use crate::{
+    constants::BASIS_POINTS, 
+    state::{
+        AllowListEntry, 
+        AllowListItem, 
+        Config
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct AllowListBatch<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AllowListBatch<'info> {
+    pub fn add_batch(&mut self, users: Vec<AllowListItem>, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Check if there is one allow list entry account for each user, in the same order
+        require!(remaining_accounts.len() == users.len(), CustomError::InvalidRemainingAccounts);
+
+        let lamports = Rent::get()?.minimum_balance(AllowListEntry::INIT_SPACE);
+        let config = self.config.key();
+
+        for (item, allow_list_entry) in users.iter().zip(remaining_accounts.iter()) {
+            // Check if the account is the allow list entry PDA of the user
+            let (address, bump) = Pubkey::find_program_address(&[b"allow", config.as_ref(), item.user.as_ref()], &crate::ID);
+            require_keys_eq!(allow_list_entry.key(), address, CustomError::InvalidAllowListEntry);
+
+            // Check if the discount is not above the full price
+            require!(item.discount_bps.unwrap_or(0) <= BASIS_POINTS, CustomError::InvalidDiscount);
+
+            if allow_list_entry.data_is_empty() {
+                // Signer seeds of the allow list entry PDA
+                let seeds = &[
+                    &b"allow"[..], 
+                    config.as_ref(),
+                    item.user.as_ref(),
+                    &[bump],
+                ];
+                let signer_seeds = &[&seeds[..]];
+
+                self.create_entry(allow_list_entry, lamports, signer_seeds)?;
+            } else {
+                // An existing entry, such as the empty one created by a mint, can be used if no user was added to it yet
+                require_keys_eq!(*allow_list_entry.owner, crate::ID, CustomError::InvalidAllowListEntry);
+                let entry = AllowListEntry::try_deserialize(&mut &allow_list_entry.try_borrow_data()?[..])?;
+                require_keys_eq!(entry.user, Pubkey::default(), CustomError::UserAlreadyAllowed);
+            }
+
+            // Write the allow list entry, including the account discriminator
+            let entry = AllowListEntry {
+                user: item.user,
+                allotted: item.amount,
+                claimed: 0,
+                discount_bps: item.discount_bps,
+                merkle: false,
+                bump,
+            };
+            let mut data = allow_list_entry.try_borrow_mut_data()?;
+            entry.try_serialize(&mut &mut data[..])?;
+        }
+
+        Ok(())
+    }
+
+    fn create_entry(&self, allow_list_entry: &AccountInfo<'info>, lamports: u64, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let cpi_program = self.system_program.to_account_info();
+
+        // Create the allow list entry account, paid by the authority
+        if allow_list_entry.lamports() == 0 {
+            let cpi_accounts = CreateAccount {
+                from: self.authority.to_account_info(),
+                to: allow_list_entry.clone(),
+            };
+            let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            return create_account(cpi_context, lamports, AllowListEntry::INIT_SPACE as u64, &crate::ID);
+        }
+
+        // The account already holds lamports, so it cannot be created. Top it up to the rent exemption, then allocate and assign it
+        let missing = lamports.saturating_sub(allow_list_entry.lamports());
+        if missing > 0 {
+            let cpi_accounts = Transfer {
+                from: self.authority.to_account_info(),
+                to: allow_list_entry.clone(),
+            };
+            transfer(CpiContext::new(cpi_program.clone(), cpi_accounts), missing)?;
+        }
+
+        let cpi_accounts = Allocate {
+            account_to_allocate: allow_list_entry.clone(),
+        };
+        allocate(CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer_seeds), AllowListEntry::INIT_SPACE as u64)?;
+
+        let cpi_accounts = Assign {
+            account_to_assign: allow_list_entry.clone(),
+        };
+        assign(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds), &crate::ID)
+    }
+}
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{
+    transfer, 
+    Transfer
+};
+
+use crate::{
+    state::{
+        AuctionReceipt, 
+        Config
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct ClaimRebate<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub authority: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"treasury", config.key().as_ref()],
+        bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"auction", config.key().as_ref(), user.key().as_ref()],
+        bump = auction_receipt.bump,
+    )]
+    pub auction_receipt: Account<'info, AuctionReceipt>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimRebate<'info> {
+    pub fn claim_rebate(&mut self, bumps: &ClaimRebateBumps) -> Result<()> {
+        let config = &mut self.config;
+        let sold_out = config.current_supply >= config.total_supply;
+        let (paid, base_paid) = (self.auction_receipt.paid, self.auction_receipt.base_paid);
+
+        // The rebate is the difference between what the user paid and the clearing price of the same mints. While the pending rebates
+        // of a replaced auction are claimed, no other rebate auction can run, so the receipt belongs to them
+        let rebate = match config.pending_rebates.as_mut() {
+            Some(pending_rebates) => {
+                let rebate = pending_rebates.rebate_amount(paid, base_paid);
+                pending_rebates.outstanding = pending_rebates.outstanding.saturating_sub(rebate);
+                pending_rebates.receipts = pending_rebates.receipts.saturating_sub(1);
+                rebate
+            },
+            None => {
+                let dutch_auction = config.dutch_auction.as_mut().filter(|dutch_auction| dutch_auction.rebate).ok_or(CustomError::NoRebate)?;
+
+                // Check if the auction has settled, either by selling out or by reaching the floor
+                require!(dutch_auction.is_settled(Clock::get()?.unix_timestamp, sold_out), CustomError::AuctionNotSettled);
+
+                let rebate = dutch_auction.rebate_amount(paid, base_paid, sold_out);
+                dutch_auction.rebated += rebate;
+                dutch_auction.receipts = dutch_auction.receipts.saturating_sub(1);
+                rebate
+            },
+        };
+
+        // Once every receipt of the pending rebates is claimed, a new rebate auction can start
+        if config.pending_rebates.is_some_and(|pending_rebates| pending_rebates.receipts == 0) {
+            config.pending_rebates = None;
+        }
+
+        // Without a rebate, the receipt is only closed
+        if rebate == 0 {
+            return Ok(());
+        }
+
+        // Create signer seeds for the treasury
+        let config_key = config.key();
+        let seeds = &[
+            &b"treasury"[..], 
+            config_key.as_ref(),
+            &[bumps.treasury],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        // Transfer the rebate from the treasury to the user. The receipt is closed afterwards
+        let cpi_program = self.system_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: self.treasury.to_account_info(),
+            to: self.user.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        transfer(cpi_context, rebate)
+    }
+}
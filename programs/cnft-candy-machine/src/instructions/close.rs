@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{
+    transfer, 
+    Transfer
+};
+
+use crate::{
+    state::{
+        Config, 
+        ConfigLines
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct Close<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"config_lines", config.key().as_ref()],
+        bump = config_lines.bump,
+    )]
+    pub config_lines: Option<Account<'info, ConfigLines>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", config.key().as_ref()],
+        bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Close<'info> {
+    pub fn close(&mut self, bumps: &CloseBumps) -> Result<()> {
+        // The candy machine can only be closed once the collection sells out
+        require!(self.config.current_supply >= self.config.total_supply, CustomError::NotSoldOut);
+
+        // With a sale goal that was not met, every payment must be refunded first
+        if let Some(sale_goal) = self.config.sale_goal {
+            require!(sale_goal.is_met() || sale_goal.outstanding_refunds() == 0, CustomError::RefundsOutstanding);
+        }
+
+        // Every rebate of a rebate auction, or the pending rebates of a replaced one, must be claimed first
+        let auction_receipts = self.config.dutch_auction.filter(|dutch_auction| dutch_auction.rebate).map_or(0, |dutch_auction| dutch_auction.receipts);
+        require!(auction_receipts == 0 && self.config.pending_rebates.is_none(), CustomError::RebatesOutstanding);
+
+        // Create signer seeds for the treasury
+        let config_key = self.config.key();
+        let seeds = &[
+            &b"treasury"[..], 
+            config_key.as_ref(),
+            &[bumps.treasury],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        // Transfer the whole treasury, including its rent exemption, to the authority. The SPL tokens of the treasury ATAs are not
+        // closed, so they must be withdrawn before. The config and config lines are closed afterwards
+        let cpi_program = self.system_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: self.treasury.to_account_info(),
+            to: self.authority.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        transfer(cpi_context, self.treasury.lamports())
+    }
+}
@@ -99,7 +99,7 @@ impl<'info> CreateCollection<'info> {
         // Signer seeds for CPI calls
         let seeds = &[
             &b"config"[..],
-            &self.authority.key.as_ref(),
+            self.authority.key.as_ref(),
             &[self.config.bump],
         ];
         let signer_seeds = &[&seeds[..]];
@@ -121,7 +121,7 @@ impl<'info> CreateCollection<'info> {
         // Create the creator array for the Collection NFT
         let creator = vec![
             Creator {
-                address: self.config.key().clone(),
+                address: self.config.key(),
                 verified: true,
                 share: 100,
             },
@@ -1,16 +1,28 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program::{
+    transfer, 
+    Transfer
+};
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{
         Mint, 
         Token
-    }
+    }, 
+    token_interface
 };
 use crate::{
+    constants::{
+        MAX_NAME_LENGTH, 
+        MAX_URI_LENGTH
+    }, 
     state::{
         Config, 
+        HiddenSettings, 
+        PaymentMint, 
         TreeStatus
     }, 
+    utils::check_payment_mint, 
     CustomError
 };
 use mpl_bubblegum::{
@@ -32,7 +44,8 @@ pub struct Initialize<'info> {
         space = Config::INIT_SPACE
     )]
     pub config: Box<Account<'info, Config>>,
-    pub allow_mint: Option<Account<'info, Mint>>,
+    pub allow_mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+    pub spl_mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
     #[account(
         init,
         payer = authority,
@@ -43,6 +56,12 @@ pub struct Initialize<'info> {
         mint::freeze_authority = config,
     )]
     pub collection: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"treasury", config.key().as_ref()],
+        bump,
+    )]
+    pub treasury: SystemAccount<'info>,
     /// CHECK: Tree Config checks will be performed by the Bubblegum Program
     #[account(mut)]
     pub tree_config: UncheckedAccount<'info>,
@@ -64,48 +83,107 @@ pub struct Initialize<'info> {
 }
 
 impl<'info> Initialize<'info> {
-    pub fn init_config(&mut self, total_supply: u32, price_sol: Option<u64>, price_spl: Option<u64>, spl_address: Option<Pubkey>, bumps: &InitializeBumps) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_config(&mut self, total_supply: u32, price_sol: Option<u64>, price_spl: Option<u64>, allow_mint_amount: Option<u64>, hidden_settings: Option<HiddenSettings>, bumps: &InitializeBumps) -> Result<()> {
         // Check if there is a mint in the allow mint account and return the key or None
-        let allow_mint = match self.allow_mint.clone() {
-            Some(value) => Some(value.key()),
-            None => None,
+        let allow_mint = self.allow_mint.as_ref().map(|value| value.key());
+
+        // Get the amount of Allow Mint tokens burned on each mint, one whole token by default
+        let allow_mint_amount = match (&self.allow_mint, allow_mint_amount) {
+            (Some(_), Some(amount)) => {
+                require!(amount > 0, CustomError::InvalidAllowMintAmount);
+                amount
+            },
+            (Some(allow_mint), None) => 10_u64.pow(allow_mint.decimals as u32),
+            (None, amount) => {
+                require!(amount.is_none(), CustomError::InvalidAllowMintAmount);
+                0
+            },
         };
 
-        // Check if there is a price and address for the SPL token and return the key or None
-        let (price_spl, spl_address) = match price_spl.is_some() && spl_address.is_some() {
-            true => (price_spl, spl_address),
-            false => {
+        // Check if there is a price and mint for the SPL token and add it as the first accepted payment mint.
+        // More payment mints can be accepted with set_payment_mints
+        let payment_mints = match (price_spl, &self.spl_mint) {
+            (Some(price_spl), Some(spl_mint)) => {
+                // The SPL token can be a Token or Token-2022 mint, as long as its extensions allow transfers
+                check_payment_mint(&spl_mint.to_account_info())?;
+                vec![PaymentMint {
+                    mint: spl_mint.key(),
+                    price: price_spl,
+                }]
+            },
+            (price_spl, spl_mint) => {
                 // If one is true and the other is false, return an error
-                require!(price_spl.is_none() && spl_address.is_none(), CustomError::InvalidSPLSettings);
-                // If both are false, return None
-                (None, None)
+                require!(price_spl.is_none() && spl_mint.is_none(), CustomError::InvalidSPLSettings);
+                // If both are false, no SPL token is accepted
+                vec![]
             },
         };
 
+        // Check if the hidden settings placeholder fits in the cNFT metadata
+        if let Some(hidden_settings) = &hidden_settings {
+            require!(hidden_settings.name.len() <= MAX_NAME_LENGTH, CustomError::InvalidHiddenSettings);
+            require!(hidden_settings.uri.len() <= MAX_URI_LENGTH, CustomError::InvalidHiddenSettings);
+        }
+
         self.config.set_inner(
             // Initialize the config account
             Config {
                 authority: self.authority.key(),
-                allow_list: vec![],
+                allow_list_root: None,
                 allow_mint,
+                allow_mint_amount,
                 collection: self.collection.key(),
                 total_supply,
                 current_supply: 0,
                 price_sol,
-                price_spl,
-                spl_address,
+                payment_mints,
+                recipients: vec![],
                 status: TreeStatus::Private,
+                start_time: None,
+                end_time: None,
+                phases: vec![],
+                max_per_wallet: None,
+                bot_tax: None,
+                gatekeeper: None,
+                token_gate: None,
+                nft_gate: None,
+                cnft_gate: None,
+                dutch_auction: None,
+                bonding_curve: None,
+                gate_discounts: vec![],
+                usd_price: None,
+                sale_goal: None,
+                hidden_settings,
+                pending_rebates: None,
                 bump: bumps.config, 
             },
         );
         Ok(())
     }
 
+    pub fn fund_treasury(&mut self) -> Result<()> {
+        // Fund the treasury with the rent exemption of an empty account, so that refunds and rebates paid from it
+        // never leave it below the rent exemption. Withdrawals keep this minimum in the treasury
+        let lamports = Rent::get()?.minimum_balance(0).saturating_sub(self.treasury.lamports());
+        if lamports == 0 {
+            return Ok(());
+        }
+
+        let cpi_program = self.system_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: self.authority.to_account_info(),
+            to: self.treasury.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+        transfer(cpi_context, lamports)
+    }
+
     pub fn init_tree(&mut self, max_depth: u32, max_buffer_size: u32) -> Result<()> {
         // Create the seeds for the CPI call
         let seeds = &[
             &b"config"[..], 
-            &self.authority.key.as_ref(),
+            self.authority.key.as_ref(),
             &[self.config.bump],
         ];
         let signer_seeds = &[&seeds[..]];
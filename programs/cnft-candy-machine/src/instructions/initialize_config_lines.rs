@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{
+    Config, 
+    ConfigLines
+};
+
+#[derive(Accounts)]
+pub struct InitializeConfigLines<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"config_lines", config.key().as_ref()],
+        bump,
+        space = ConfigLines::INIT_SPACE,
+    )]
+    pub config_lines: Account<'info, ConfigLines>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeConfigLines<'info> {
+    pub fn initialize_config_lines(&mut self, random: bool, bumps: &InitializeConfigLinesBumps) -> Result<()> {
+        // Initialize the config lines account without any lines. Lines are added through `add_config_lines`
+        self.config_lines.set_inner(
+            ConfigLines {
+                line_count: 0,
+                random,
+                bump: bumps.config_lines,
+            }
+        );
+        Ok(())
+    }
+}
@@ -1,44 +1,76 @@
 use anchor_lang::prelude::*;
 
-use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::ed25519_program::ID as ED25519_PROGRAM_ID;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, 
+    load_instruction_at_checked, 
+    ID as INSTRUCTIONS_SYSVAR_ID
+};
+use anchor_lang::solana_program::sysvar::slot_hashes::ID as SLOT_HASHES_ID;
 use anchor_lang::system_program::{
     Transfer,
     transfer, 
 };
-use anchor_spl::token::spl_token::instruction::transfer as spl_transfer;
 use anchor_spl::associated_token::{
-    get_associated_token_address, 
-    AssociatedToken
+    create_idempotent, 
+    get_associated_token_address_with_program_id, 
+    AssociatedToken, 
+    Create
 };
 use anchor_spl::metadata::{
     MasterEditionAccount, 
     Metadata, 
     MetadataAccount
 };
-use anchor_spl::token::{
+use anchor_spl::token::Mint;
+use anchor_spl::token_interface::{
+    self, 
     burn, 
+    transfer_checked, 
     Burn, 
-    Mint, 
-    Token, TokenAccount
+    TokenInterface, 
+    TransferChecked
 };
-use mpl_bubblegum::instructions::MintToCollectionV1CpiBuilder;
-use mpl_bubblegum::types::{
-    Collection, 
-    MetadataArgs, 
-    TokenProgramVersion, 
-    TokenStandard
+use mpl_bubblegum::instructions::{
+    MintToCollectionV1CpiBuilder, 
+    VerifyLeafCpiBuilder
 };
+use mpl_bubblegum::types::LeafSchema;
+use mpl_bubblegum::utils::get_asset_id;
 use mpl_bubblegum::ID as BUBBLEGUM_ID;
 use spl_account_compression::ID as SPL_ACCOUNT_COMPRESSION_ID;
 use spl_noop::ID as SPL_NOOP_ID;
 
-use crate::state::TreeStatus;
+use crate::state::{
+    AllowListEntry, 
+    AllowListProof, 
+    AuctionReceipt, 
+    GoalReceipt, 
+    CnftGateProof, 
+    ConfigLine, 
+    ConfigLines, 
+    Gate, 
+    GateReceipt, 
+    GatekeeperMode, 
+    GatekeeperNonce, 
+    MintCounter, 
+    PaymentMint, 
+    TreeStatus
+};
 use crate::{
+    constants::BASIS_POINTS, 
     state::Config, 
+    utils::{
+        apply_bps, 
+        payment_gross_amount, 
+        usd_price_in_lamports
+    }, 
     CustomError
 };
 
 #[derive(Accounts)]
+#[instruction(currency: Option<Pubkey>, max_price: Option<u64>, allow_list_proof: Option<AllowListProof>, cnft_gate_proof: Option<CnftGateProof>)]
 pub struct MintNFT<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
@@ -48,11 +80,121 @@ pub struct MintNFT<'info> {
         seeds = [b"config", authority.key().as_ref()],
         bump = config.bump,
     )]
-    pub config: Account<'info, Config>,
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", config.key().as_ref()],
+        bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"config_lines", config.key().as_ref()],
+        bump = config_lines.bump,
+    )]
+    pub config_lines: Option<Account<'info, ConfigLines>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"allow", config.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = AllowListEntry::INIT_SPACE,
+    )]
+    pub allow_list_entry: Option<Account<'info, AllowListEntry>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [config.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = MintCounter::INIT_SPACE,
+    )]
+    pub mint_counter: Option<Account<'info, MintCounter>>,
+    pub gatekeeper: Option<Signer<'info>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"gatekeeper", config.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = GatekeeperNonce::INIT_SPACE,
+    )]
+    pub gatekeeper_nonce: Option<Account<'info, GatekeeperNonce>>,
+    /// CHECK: Instructions sysvar checked by the corresponding address
+    #[account(address = INSTRUCTIONS_SYSVAR_ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub token_gate_account: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"token_gate", config.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = GateReceipt::INIT_SPACE,
+    )]
+    pub token_gate_receipt: Option<Account<'info, GateReceipt>>,
+    pub token_gate_mint: Option<Box<InterfaceAccount<'info, token_interface::Mint>>>,
+    /// CHECK: Token gate escrow ATA checked against the expected address and created if needed
+    #[account(mut)]
+    pub token_gate_escrow: Option<UncheckedAccount<'info>>,
+    pub token_gate_token_program: Option<Interface<'info, TokenInterface>>,
+    pub nft_gate_mint: Option<Box<InterfaceAccount<'info, token_interface::Mint>>>,
+    pub nft_gate_account: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
+    pub nft_gate_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"nft_gate", config.key().as_ref(), nft_gate_mint.as_ref().map(|mint| mint.key()).unwrap_or_default().as_ref()],
+        bump,
+        space = GateReceipt::INIT_SPACE,
+    )]
+    pub nft_gate_receipt: Option<Account<'info, GateReceipt>>,
+    /// CHECK: cNFT gate Merkle Tree account checked against the config and by the SPL Account Compression Program
+    pub cnft_gate_merkle_tree: Option<UncheckedAccount<'info>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [
+            b"cnft_gate", 
+            config.key().as_ref(), 
+            get_asset_id(
+                &cnft_gate_merkle_tree.as_ref().map(|merkle_tree| merkle_tree.key()).unwrap_or_default(), 
+                cnft_gate_proof.as_ref().map_or(0, |cnft_gate_proof| cnft_gate_proof.nonce)
+            ).as_ref()
+        ],
+        bump,
+        space = GateReceipt::INIT_SPACE,
+    )]
+    pub cnft_gate_receipt: Option<Account<'info, GateReceipt>>,
     #[account(mut)]
-    pub allow_mint: Option<Account<'info, Mint>>,
+    pub allow_mint: Option<Box<InterfaceAccount<'info, token_interface::Mint>>>,
     #[account(mut)]
-    pub allow_mint_ata: Option<Account<'info, TokenAccount>>,
+    pub allow_mint_ata: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
+    pub allow_mint_token_program: Option<Interface<'info, TokenInterface>>,
+    pub payment_mint: Option<Box<InterfaceAccount<'info, token_interface::Mint>>>,
+    #[account(mut)]
+    pub payment_source: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
+    /// CHECK: Treasury ATA checked against the expected address and created if needed
+    #[account(mut)]
+    pub payment_destination: Option<UncheckedAccount<'info>>,
+    pub payment_token_program: Option<Interface<'info, TokenInterface>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"auction", config.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = AuctionReceipt::INIT_SPACE,
+    )]
+    pub auction_receipt: Option<Account<'info, AuctionReceipt>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"goal", config.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = GoalReceipt::INIT_SPACE,
+    )]
+    pub goal_receipt: Option<Account<'info, GoalReceipt>>,
+    /// CHECK: Price oracle checked against the config and parsed as a Pyth price account
+    pub price_oracle: Option<UncheckedAccount<'info>>,
     #[account(
         mut,
         seeds = [b"collection", config.key().as_ref()],
@@ -69,7 +211,7 @@ pub struct MintNFT<'info> {
         seeds::program = metadata_program.key(),
         bump,
     )]
-    pub collection_metadata: Account<'info, MetadataAccount>,
+    pub collection_metadata: Box<Account<'info, MetadataAccount>>,
     #[account(
         mut,
         seeds = [
@@ -81,7 +223,7 @@ pub struct MintNFT<'info> {
         seeds::program = metadata_program.key(),
         bump,
     )]
-    pub collection_edition: Account<'info, MasterEditionAccount>,
+    pub collection_edition: Box<Account<'info, MasterEditionAccount>>,
     /// CHECK: Tree Config account that will be checked by the Bubblegum Program
     #[account(mut)]
     pub tree_config: UncheckedAccount<'info>,
@@ -97,57 +239,83 @@ pub struct MintNFT<'info> {
     /// CHECK: SPL Account Compression Program checked by the corresponding address
     #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
     pub compression_program: UncheckedAccount<'info>,
+    /// CHECK: SlotHashes sysvar checked by the corresponding address
+    #[account(address = SLOT_HASHES_ID)]
+    pub recent_slothashes: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
     pub metadata_program: Program<'info, Metadata>,
 }
 
 impl<'info> MintNFT<'info> {
-    pub fn mint_cnft(&mut self, name: String, symbol: String, uri: String, pay_sol: bool, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+    pub fn mint_cnft(&mut self, currency: Option<Pubkey>, max_price: Option<u64>, allow_list_proof: Option<AllowListProof>, cnft_gate_proof: Option<CnftGateProof>, bumps: &MintNFTBumps, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
 
-        // Check if the Candy Machine is active
-        require!(self.config.status != TreeStatus::Inactive, CustomError::CandyMachineInactive);
+        // With recipients, the first remaining accounts are the recipient accounts that share the payment (wallets when paying in SOL, 
+        // each wallet followed by its ATA when paying in SPL). The other remaining accounts are the proof of the cNFT gate leaf
+        let recipient_account_count = match currency {
+            Some(_) => self.config.recipients.len() * 2,
+            None => self.config.recipients.len(),
+        };
+        let (recipient_accounts, proof_accounts) = remaining_accounts.split_at(recipient_account_count.min(remaining_accounts.len()));
 
-        // Check if the Candy Machine is private
-        if self.config.status == TreeStatus::Private {
-            // Check if there is an Allow Mint account and Allow Mint ATA account
-            if let (Some(allow_mint), Some(allow_mint_ata)) = (&self.allow_mint, &self.allow_mint_ata) {
+        // Check if the user is allowed to mint and get the prices of the mint. If a gate check fails and the config has a bot tax, 
+        // the bot tax is charged and the instruction succeeds without minting
+        let (price_sol, payment_mints) = match self.check_gates(allow_list_proof, cnft_gate_proof, bumps, proof_accounts) {
+            Ok(prices) => prices,
+            Err(error) => match self.config.bot_tax {
+                Some(bot_tax) if is_bot_taxable(&error) => {
+                    msg!("Bot tax charged: {}", error);
+                    return self.pay_bot_tax(bot_tax);
+                },
+                _ => return Err(error),
+            },
+        };
 
-                // Check if the Allow Mint account is the same as the one in the config
-                require!(allow_mint.key() == self.config.allow_mint.unwrap(), CustomError::InvalidAllowMint);
+        // With a bonding curve, the prices rise with the current supply. With a Dutch auction, they then decay to the floor 
+        // over the auction schedule
+        let now = Clock::get()?.unix_timestamp;
+        let curve_bps = self.config.bonding_curve.as_ref().map_or(BASIS_POINTS as u64, |bonding_curve| bonding_curve.price_bps(self.config.current_supply));
+        let price_bps = self.config.dutch_auction.map_or(BASIS_POINTS as u64, |dutch_auction| dutch_auction.price_bps(now));
+        let base_price_sol = price_sol.map(|price| apply_bps(price, curve_bps));
+        let price_sol = base_price_sol.map(|price| apply_bps(price, price_bps));
+        let payment_mints = payment_mints
+            .into_iter()
+            .map(|payment_mint| PaymentMint {
+                mint: payment_mint.mint,
+                price: apply_bps(apply_bps(payment_mint.price, curve_bps), price_bps),
+            })
+            .collect::<Vec<_>>();
 
-                // Check if the Allow Mint ATA account belongs to the user
-                let ata_address = get_associated_token_address(&self.user.key(), &allow_mint.key());
-                require!(ata_address == self.allow_mint_ata.as_ref().unwrap().key(), CustomError::InvalidAllowMintATA);
+        // Get the name and uri of the cNFT. With hidden settings every cNFT gets the placeholder until it is revealed,
+        // otherwise the next config line is used
+        let line = match &self.config.hidden_settings {
+            Some(hidden_settings) => ConfigLine {
+                name: hidden_settings.name.clone(),
+                uri: hidden_settings.uri.clone(),
+            },
+            None => {
+                let config_lines = self.config_lines.as_ref().ok_or(CustomError::ConfigLinesNotLoaded)?;
+                
+                // Check if all the config lines have been loaded
+                require!(config_lines.line_count == self.config.total_supply, CustomError::ConfigLinesNotLoaded);
 
-                // Burn the Allow Mint token
-                let cpi_program = self.token_program.to_account_info();
-                let cpi_accounts = Burn {
-                    mint: allow_mint.to_account_info(),
-                    from: allow_mint_ata.to_account_info(),
-                    authority: self.user.to_account_info(),
+                // In random order, draw one of the remaining lines, otherwise take the next one
+                let index = match config_lines.random {
+                    true => self.draw_random_line(&config_lines.to_account_info())?,
+                    false => self.config.current_supply,
                 };
-                let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
-                burn(cpi_context, 1 * 10_u32.pow(allow_mint.decimals as u32) as u64)?;
-            }
-            else {
-                // If the Candy Machine is private and there is no Allow Mint account, check if the user is in the Allow List
-                self.config.allow_list.iter().find(|x| x.user == self.user.key()).ok_or(CustomError::UserNotAllowed)?;
-
-                // Check if the user has already claimed
-                let user_struct = self.config.allow_list.iter_mut().find(|x| x.user == self.user.key()).unwrap();      
-                if user_struct.amount == 0 {
-                    return Err(CustomError::AlreadyClaimed.into());
-                }
-                // Decrease the allowed amount of the user
-                user_struct.amount -= 1;    
-            }
-        }
+
+                ConfigLines::read_line(&config_lines.to_account_info(), index)?
+            },
+        };
+
+        // Use the collection symbol for every cNFT
+        let symbol = self.collection_metadata.symbol.trim_matches(char::from(0)).to_string();
+        let metadata = self.config.metadata_args(line.name, symbol, line.uri);
 
         // Create signer seeds for the CPI calls
         let seeds = &[
             &b"config"[..], 
-            &self.authority.key.as_ref(),
+            self.authority.key.as_ref(),
             &[self.config.bump],
         ];
         let signer_seeds = &[&seeds[..]];
@@ -170,116 +338,656 @@ impl<'info> MintNFT<'info> {
             .compression_program(&self.compression_program.to_account_info())
             .token_metadata_program(&self.metadata_program.to_account_info())
             .system_program(&self.system_program.to_account_info())
-            .metadata(
-                MetadataArgs {
-                    name,
-                    symbol,
-                    uri,
-                    creators: vec![],
-                    seller_fee_basis_points: 0,
-                    primary_sale_happened: false,
-                    is_mutable: false,
-                    edition_nonce: Some(0),
-                    uses: None,
-                    collection: Some(Collection {
-                        verified: true,
-                        key: self.collection.key(),
-                    }),
-                    token_program_version: TokenProgramVersion::Original,
-                    token_standard: Some(TokenStandard::NonFungible),
-                }
-            )
+            .metadata(metadata)
         .invoke_signed(signer_seeds)?;
 
-        // Check if the user wants to pay in SOL or SPL and if there is a price in SOL or SPL. Return an error if the settings are invalid
-        match pay_sol {
+        // Check the currency the user wants to pay with and if there is a price in that currency. Return an error if the settings are invalid
+        // or if the price is above the max price the user accepts
+        match currency {
             // If the user wants to pay in SOL, check if there is a price in SOL. 
-            // If there is, transfer the SOL to the authority, otherwise check if there is a price in SPL and return an error if there is
-            true => match self.config.price_sol.is_some() {
-                true => self.transfer_sol()?,
-                false => require!(self.config.price_spl.is_none(), CustomError::InvalidSPLSettings),
+            // If there is, transfer the SOL to the recipients, otherwise check if there is a price in SPL and return an error if there is
+            None => match price_sol {
+                Some(price_sol) => {
+                    require!(price_sol <= max_price.unwrap_or(u64::MAX), CustomError::PriceExceedsMax);
+                    self.transfer_sol(price_sol, recipient_accounts)?;
+                    self.record_auction_payment(price_sol, base_price_sol.unwrap(), bumps)?;
+                    self.record_goal_payment(price_sol, bumps)?;
+                },
+                None => require!(payment_mints.is_empty(), CustomError::InvalidSPLSettings),
             },
-            // If the user wants to pay in SPL, check if there is a price in SPL and an SPL address. 
-            // If there is, transfer the SPL to the authority, otherwise check if there is a price in SOL and return an error if there is
-            false => match self.config.price_spl.is_some() && self.config.spl_address.is_some() {
-                true => self.transfer_spl(remaining_accounts)?,
-                false => require!(self.config.price_sol.is_none(), CustomError::InvalidSPLSettings),
+            // If the user wants to pay in SPL, check if the mint is one of the accepted payment mints. 
+            // If it is, transfer its price to the recipients, otherwise check if the mint is free and return an error if it is not
+            Some(currency) => match payment_mints.iter().find(|payment_mint| payment_mint.mint == currency) {
+                Some(payment_mint) => {
+                    require!(self.config.sale_goal.is_none(), CustomError::SaleGoalSolOnly);
+                    self.transfer_spl(payment_mint.mint, payment_mint.price, max_price, recipient_accounts)?;
+                },
+                None => require!(price_sol.is_none() && payment_mints.is_empty(), CustomError::InvalidPaymentMint),
             },
         }
 
+        // The price of the last mint is the clearing price of the auction if the collection sells out
+        if let Some(dutch_auction) = self.config.dutch_auction.as_mut() {
+            dutch_auction.clearing_bps = price_bps as u16;
+        }
+
         // Increase the current supply
         self.config.current_supply += 1;
 
-        // If the total supply is equal to the current supply, close the account
-        if self.config.current_supply >= self.config.total_supply {
-            self.close_account()?;
+        Ok(())
+    }
+
+    pub fn draw_random_line(&self, config_lines: &AccountInfo<'info>) -> Result<u32> {
+        // Use the most recent slot hash (after the u64 length prefix and the u64 slot) combined with the minter key as entropy
+        let data = self.recent_slothashes.try_borrow_data()?;
+        let recent_slothash = &data[16..48];
+        let hash = hashv(&[recent_slothash, self.user.key.as_ref(), &self.config.current_supply.to_le_bytes()]);
+        let seed = u64::from_le_bytes(hash.to_bytes()[..8].try_into().unwrap());
+
+        // Pick one of the remaining line indices and swap the last remaining one into its place
+        let remaining = self.config.total_supply.saturating_sub(self.config.current_supply);
+        require!(remaining > 0, CustomError::SoldOut);
+        let position = (seed % remaining as u64) as u32;
+        ConfigLines::swap_remove(config_lines, position, remaining - 1)
+    }
+
+    pub fn check_gates(&mut self, allow_list_proof: Option<AllowListProof>, cnft_gate_proof: Option<CnftGateProof>, bumps: &MintNFTBumps, proof_accounts: &[AccountInfo<'info>]) -> Result<(Option<u64>, Vec<PaymentMint>)> {
+        // Check if the collection is not sold out. With hidden settings, the config is never closed, so this is the only supply cap
+        require!(self.config.current_supply < self.config.total_supply, CustomError::SoldOut);
+
+        // Gate receipts are derived from the gate accounts, so they can only be passed along with them
+        require!(self.nft_gate_receipt.is_none() || self.nft_gate_mint.is_some(), CustomError::NftGateRequired);
+        require!(
+            self.cnft_gate_receipt.is_none() || (self.cnft_gate_merkle_tree.is_some() && cnft_gate_proof.is_some()), 
+            CustomError::CnftGateRequired
+        );
+
+        // Check if the Candy Machine is active
+        require!(self.config.status != TreeStatus::Inactive, CustomError::CandyMachineInactive);
+
+        // Check if the current time is within the sale window
+        let now = Clock::get()?.unix_timestamp;
+        if let Some(start_time) = self.config.start_time {
+            require!(now >= start_time, CustomError::SaleNotStarted);
+        }
+        if let Some(end_time) = self.config.end_time {
+            require!(now < end_time, CustomError::SaleEnded);
+        }
+
+        // Check if the sale goal has not failed, otherwise the buyers are being refunded
+        if let Some(sale_goal) = self.config.sale_goal {
+            require!(!sale_goal.is_failed(now), CustomError::SaleGoalFailed);
+        }
+
+        // Check if the gatekeeper approved the mint
+        self.check_gatekeeper(now, bumps)?;
+
+        // Resolve the gate and prices of the mint. If phases are configured, they are taken from the active phase,
+        // otherwise from the Candy Machine status and config
+        let (phase, gate, price_sol, payment_mints) = match self.config.phases.is_empty() {
+            false => {
+                let index = self.config.active_phase(now).ok_or(CustomError::NoActivePhase)?;
+                let phase = &self.config.phases[index];
+
+                // Check if the phase supply cap has been reached
+                if let Some(supply_cap) = phase.supply_cap {
+                    require!(phase.minted < supply_cap, CustomError::PhaseSoldOut);
+                }
+
+                (Some(index), phase.gate, phase.price_sol.or(self.config.price_sol), self.config.phase_payment_mints(phase))
+            },
+            true => {
+                // If the Candy Machine is private, the user mints by burning an Allow Mint token if the Allow Mint accounts are provided, 
+                // by holding the gate token, NFT or cNFT if the corresponding gate account is provided, otherwise with the Allow List
+                let gate = match self.config.status {
+                    TreeStatus::Private if self.allow_mint.is_some() && self.allow_mint_ata.is_some() => Gate::AllowMint,
+                    TreeStatus::Private if self.token_gate_account.is_some() => Gate::TokenHolder,
+                    TreeStatus::Private if self.nft_gate_account.is_some() => Gate::NftHolder,
+                    TreeStatus::Private if self.cnft_gate_merkle_tree.is_some() => Gate::CnftHolder,
+                    TreeStatus::Private => Gate::AllowList,
+                    _ => Gate::Public,
+                };
+
+                (None, gate, self.config.price_sol, self.config.payment_mints.clone())
+            },
+        };
+
+        // Check if the user passes the gate
+        match gate {
+            Gate::Public => self.count_public_mint(bumps)?,
+            Gate::AllowMint => self.burn_allow_mint()?,
+            Gate::AllowList => self.claim_allow_list(allow_list_proof, bumps)?,
+            Gate::TokenHolder => self.check_token_holder(bumps)?,
+            Gate::NftHolder => self.check_nft_holder(bumps)?,
+            Gate::CnftHolder => self.check_cnft_holder(cnft_gate_proof, bumps, proof_accounts)?,
+        }
+
+        // Increase the minted amount of the active phase
+        if let Some(index) = phase {
+            self.config.phases[index].minted += 1;
+        }
+
+        // With USD pricing, the SOL price is the USD price converted at the SOL price of the oracle
+        let price_sol = match self.config.usd_price {
+            Some(usd_price) => {
+                let price_oracle = self.price_oracle.as_ref().ok_or(CustomError::InvalidOracle)?;
+                require_keys_eq!(price_oracle.key(), usd_price.oracle, CustomError::InvalidOracle);
+                Some(usd_price_in_lamports(price_oracle, &usd_price, now)?)
+            },
+            None => price_sol,
+        };
+
+        // Allow listed wallets can have their own discount, otherwise the discount of the gate is applied
+        let discount_bps = match gate {
+            Gate::AllowList => self.allow_list_entry.as_ref().and_then(|allow_list_entry| allow_list_entry.discount_bps),
+            _ => None,
+        }.or(self.config.gate_discount(gate)).unwrap_or(0);
+
+        // With a full discount the mint is free
+        if discount_bps == BASIS_POINTS {
+            return Ok((None, vec![]));
+        }
+
+        let price_bps = (BASIS_POINTS - discount_bps) as u64;
+        let price_sol = price_sol.map(|price| apply_bps(price, price_bps));
+        let payment_mints = payment_mints
+            .into_iter()
+            .map(|payment_mint| PaymentMint {
+                mint: payment_mint.mint,
+                price: apply_bps(payment_mint.price, price_bps),
+            })
+            .collect();
+
+        Ok((price_sol, payment_mints))
+    }
+
+    pub fn check_gatekeeper(&mut self, now: i64, bumps: &MintNFTBumps) -> Result<()> {
+        let Some(gatekeeper) = self.config.gatekeeper else {
+            return Ok(());
+        };
+
+        match gatekeeper.mode {
+            // The gatekeeper must co-sign the transaction
+            GatekeeperMode::CoSigner => {
+                let signer = self.gatekeeper.as_ref().ok_or(CustomError::GatekeeperRequired)?;
+                require_keys_eq!(signer.key(), gatekeeper.key, CustomError::InvalidGatekeeperSignature);
+            },
+            // The gatekeeper signs (config, user, expiry, nonce) off-chain, and the signature is verified by an Ed25519 Program 
+            // instruction placed right before the mint instruction
+            GatekeeperMode::Ed25519 => {
+                let instructions_sysvar = self.instructions_sysvar.as_ref().ok_or(CustomError::GatekeeperRequired)?;
+                let current_index = load_current_index_checked(instructions_sysvar)?;
+                require!(current_index > 0, CustomError::GatekeeperRequired);
+                let instruction = load_instruction_at_checked(current_index as usize - 1, instructions_sysvar)?;
+                require_keys_eq!(instruction.program_id, ED25519_PROGRAM_ID, CustomError::GatekeeperRequired);
+
+                // Check if the signature was made by the gatekeeper over the expected message
+                let (public_key, message) = parse_ed25519_instruction(&instruction.data).ok_or(CustomError::InvalidGatekeeperSignature)?;
+                require_keys_eq!(public_key, gatekeeper.key, CustomError::InvalidGatekeeperSignature);
+                require!(message.len() == 80, CustomError::InvalidGatekeeperSignature);
+                require!(message[..32] == self.config.key().to_bytes(), CustomError::InvalidGatekeeperSignature);
+                require!(message[32..64] == self.user.key().to_bytes(), CustomError::InvalidGatekeeperSignature);
+
+                // Check if the signature has not expired
+                let expiry = i64::from_le_bytes(message[64..72].try_into().unwrap());
+                require!(now <= expiry, CustomError::GatekeeperSignatureExpired);
+
+                // Reject replays. Nonces of each user must be strictly increasing, and the last one is kept in the user gatekeeper nonce account
+                let nonce = u64::from_le_bytes(message[72..80].try_into().unwrap());
+                let gatekeeper_nonce = self.gatekeeper_nonce.as_mut().ok_or(CustomError::GatekeeperRequired)?;
+                require!(nonce > gatekeeper_nonce.last_nonce, CustomError::GatekeeperNonceUsed);
+                gatekeeper_nonce.last_nonce = nonce;
+                gatekeeper_nonce.bump = bumps.gatekeeper_nonce.unwrap();
+            },
         }
 
         Ok(())
     }
 
-    pub fn transfer_sol(&mut self) -> Result<()> {
-        // Transfer the SOL to the authority
+    pub fn pay_bot_tax(&mut self, bot_tax: u64) -> Result<()> {
+        // Transfer the bot tax to the treasury
         let cpi_program = self.system_program.to_account_info();
 
         let cpi_accounts = Transfer {
             from: self.user.to_account_info(),
-            to: self.authority.to_account_info(),
+            to: self.treasury.to_account_info(),
         };
 
         let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
 
-        transfer(cpi_context, self.config.price_sol.unwrap())
+        transfer(cpi_context, bot_tax)
+    }
+
+    pub fn count_public_mint(&mut self, bumps: &MintNFTBumps) -> Result<()> {
+        // Check if there is a limit of cNFTs per wallet
+        let Some(max_per_wallet) = self.config.max_per_wallet else {
+            return Ok(());
+        };
+
+        // The amount minted by the user is tracked in the user mint counter, created on the first mint
+        let mint_counter = self.mint_counter.as_mut().ok_or(CustomError::MintCounterRequired)?;
+        mint_counter.bump = bumps.mint_counter.unwrap();
+
+        // Check if the user has reached the limit and increase the minted amount
+        require!(mint_counter.count < max_per_wallet, CustomError::MintLimitReached);
+        mint_counter.count += 1;
+
+        Ok(())
     }
 
-    pub fn transfer_spl(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
-        // Check if there are 2 remaining accounts
-        if remaining_accounts.len() != 2 {
-            return Err(CustomError::InvalidRemainingAccounts.into());
+    pub fn burn_allow_mint(&mut self) -> Result<()> {
+        // Check if there is an Allow Mint account, Allow Mint ATA account and token program of the Allow Mint
+        let (Some(allow_mint), Some(allow_mint_ata), Some(token_program)) = (&self.allow_mint, &self.allow_mint_ata, &self.allow_mint_token_program) else {
+            return Err(CustomError::InvalidAllowMint.into());
+        };
+
+        // Check if the Allow Mint account is the same as the one in the config
+        require!(Some(allow_mint.key()) == self.config.allow_mint, CustomError::InvalidAllowMint);
+
+        // Check if the Allow Mint ATA account belongs to the user. The Allow Mint can be a Token or Token-2022 mint
+        let ata_address = get_associated_token_address_with_program_id(&self.user.key(), &allow_mint.key(), token_program.key);
+        require!(ata_address == allow_mint_ata.key(), CustomError::InvalidAllowMintATA);
+
+        // Burn the configured amount of Allow Mint tokens
+        let cpi_program = token_program.to_account_info();
+        let cpi_accounts = Burn {
+            mint: allow_mint.to_account_info(),
+            from: allow_mint_ata.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+        burn(cpi_context, self.config.allow_mint_amount)
+    }
+
+    pub fn check_token_holder(&mut self, bumps: &MintNFTBumps) -> Result<()> {
+        let token_gate = self.config.token_gate.ok_or(CustomError::TokenGateRequired)?;
+        let token_account = self.token_gate_account.as_ref().ok_or(CustomError::TokenGateRequired)?;
+
+        // Check if the token account holds the gate token and belongs to the user
+        require_keys_eq!(token_account.mint, token_gate.mint, CustomError::InvalidTokenGateAccount);
+        require_keys_eq!(token_account.owner, self.user.key(), CustomError::InvalidTokenGateAccount);
+
+        // Check if the user holds enough tokens. The tokens are not burned
+        require!(token_account.amount >= token_gate.amount, CustomError::NotEnoughTokens);
+
+        // With single use, the claim is recorded in the user token gate receipt and the tokens are locked in an escrow
+        // owned by the receipt until the sale is over, so the same tokens cannot claim again from another wallet
+        if token_gate.single_use {
+            let receipt = self.token_gate_receipt.as_mut().ok_or(CustomError::TokenGateRequired)?;
+            require!(!receipt.used, CustomError::TokenGateAlreadyClaimed);
+            receipt.used = true;
+            receipt.bump = bumps.token_gate_receipt.unwrap();
+
+            self.lock_token_gate(token_gate.mint, token_gate.amount)?;
         }
 
-        // Get the expected ATA accounts
-        let expected_from_ata = get_associated_token_address(&self.user.key, &self.config.spl_address.as_ref().unwrap());
-        let expected_to_ata = get_associated_token_address(&self.authority.key, &self.config.spl_address.as_ref().unwrap());
-
-        // Check if the first remaining accounts are is the expected source ATA
-        require_keys_eq!(remaining_accounts[0].key(), expected_from_ata, CustomError::InvalidSourceRemainingAccount);
-
-        // Check if the second remaining account is the expected destination ATA
-        require_keys_eq!(remaining_accounts[1].key(), expected_to_ata, CustomError::InvalidDestinationRemainingAccount);
-
-
-        // Create the transfer instruction
-        let transfer_tokens_instruction = spl_transfer(
-            &self.token_program.key,
-            &remaining_accounts[0].key(),
-            &remaining_accounts[1].key(),
-            &self.user.key(),
-            &[&self.user.key()],
-            self.config.price_spl.unwrap(),
-        )?;
-        
-        // Collect the required accounts for the transfer
-        let required_accounts_for_transfer = [
-            remaining_accounts[0].to_account_info().clone(),
-            remaining_accounts[1].to_account_info().clone(),
-            self.user.to_account_info().clone(),
-        ];
-        
-        // Invoke the transfer instruction
-        invoke(
-            &transfer_tokens_instruction,
-            &required_accounts_for_transfer,
-        )?;
+        Ok(())
+    }
+
+    pub fn lock_token_gate(&self, gate_mint: Pubkey, amount: u64) -> Result<()> {
+        let (Some(token_account), Some(receipt), Some(mint), Some(escrow), Some(token_program), Some(associated_token_program)) = (
+            &self.token_gate_account,
+            &self.token_gate_receipt,
+            &self.token_gate_mint,
+            &self.token_gate_escrow,
+            &self.token_gate_token_program,
+            &self.associated_token_program,
+        ) else {
+            return Err(CustomError::TokenGateRequired.into());
+        };
+
+        // Check if the escrow is the ATA of the receipt for the gate mint
+        require_keys_eq!(mint.key(), gate_mint, CustomError::InvalidTokenGateAccount);
+        require_keys_eq!(
+            escrow.key(),
+            get_associated_token_address_with_program_id(&receipt.key(), &mint.key(), &token_program.key()),
+            CustomError::InvalidTokenGateAccount
+        );
+
+        // Create the escrow if it does not exist yet. The user pays for it and gets the rent back on release
+        let cpi_program = associated_token_program.to_account_info();
+        let cpi_accounts = Create {
+            payer: self.user.to_account_info(),
+            associated_token: escrow.to_account_info(),
+            authority: receipt.to_account_info(),
+            mint: mint.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            token_program: token_program.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+        create_idempotent(cpi_context)?;
+
+        // Transfer the gate tokens from the user to the escrow
+        let cpi_program = token_program.to_account_info();
+        let cpi_accounts = TransferChecked {
+            from: token_account.to_account_info(),
+            mint: mint.to_account_info(),
+            to: escrow.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+        transfer_checked(cpi_context, amount, mint.decimals)
+    }
+
+    pub fn check_nft_holder(&mut self, bumps: &MintNFTBumps) -> Result<()> {
+        let nft_gate = self.config.nft_gate.ok_or(CustomError::NftGateRequired)?;
+        let (Some(nft_mint), Some(token_account), Some(metadata)) = (&self.nft_gate_mint, &self.nft_gate_account, &self.nft_gate_metadata) else {
+            return Err(CustomError::NftGateRequired.into());
+        };
+
+        // Check if the mint is an NFT held by the user
+        require!(nft_mint.decimals == 0 && nft_mint.supply == 1, CustomError::InvalidNftGateAccount);
+        require_keys_eq!(token_account.mint, nft_mint.key(), CustomError::InvalidNftGateAccount);
+        require_keys_eq!(token_account.owner, self.user.key(), CustomError::InvalidNftGateAccount);
+        require!(token_account.amount == 1, CustomError::InvalidNftGateAccount);
+
+        // Check if the metadata belongs to the NFT and its verified collection is the gate collection
+        require_keys_eq!(metadata.mint, nft_mint.key(), CustomError::InvalidNftGateAccount);
+        require!(
+            metadata.collection.as_ref().is_some_and(|collection| collection.verified && collection.key == nft_gate.collection),
+            CustomError::InvalidNftCollection
+        );
+
+        // With single use, each NFT can only unlock one mint, and its use is recorded in the NFT gate receipt
+        if nft_gate.single_use {
+            let receipt = self.nft_gate_receipt.as_mut().ok_or(CustomError::NftGateRequired)?;
+            require!(!receipt.used, CustomError::NftGateAlreadyUsed);
+            receipt.used = true;
+            receipt.bump = bumps.nft_gate_receipt.unwrap();
+        }
+
+        Ok(())
+    }
+
+    pub fn check_cnft_holder(&mut self, cnft_gate_proof: Option<CnftGateProof>, bumps: &MintNFTBumps, proof_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let cnft_gate = self.config.cnft_gate.ok_or(CustomError::CnftGateRequired)?;
+        let (Some(merkle_tree), Some(cnft_gate_proof)) = (&self.cnft_gate_merkle_tree, cnft_gate_proof) else {
+            return Err(CustomError::CnftGateRequired.into());
+        };
+
+        // Check if the Merkle Tree is the one in the config
+        require_keys_eq!(merkle_tree.key(), cnft_gate.merkle_tree, CustomError::InvalidCnftGateTree);
+
+        // Bubblegum uses the leaf index as the nonce, so the receipt derived from the nonce is the one of the verified leaf
+        require!(cnft_gate_proof.index as u64 == cnft_gate_proof.nonce, CustomError::InvalidCnftGateProof);
+
+        // Rebuild the leaf of the cNFT owned by the user. Unless delegated, the leaf delegate is the owner
+        let leaf = LeafSchema::V1 {
+            id: get_asset_id(merkle_tree.key, cnft_gate_proof.nonce),
+            owner: self.user.key(),
+            delegate: cnft_gate_proof.delegate.unwrap_or(self.user.key()),
+            nonce: cnft_gate_proof.nonce,
+            data_hash: cnft_gate_proof.data_hash,
+            creator_hash: cnft_gate_proof.creator_hash,
+        };
+
+        // Proof accounts for the leaf
+        let proof = proof_accounts
+            .iter()
+            .map(|account| (account, false, false))
+            .collect::<Vec<_>>();
+
+        // CPI call to the SPL Account Compression Program to verify the leaf is in the Merkle Tree
+        VerifyLeafCpiBuilder::new(&self.compression_program.to_account_info())
+            .merkle_tree(&merkle_tree.to_account_info())
+            .root(cnft_gate_proof.root)
+            .leaf(leaf.hash())
+            .index(cnft_gate_proof.index)
+            .add_remaining_accounts(&proof)
+            .invoke()?;
+
+        // With single use, each cNFT can only unlock one mint, and its use is recorded in the cNFT gate receipt
+        if cnft_gate.single_use {
+            let receipt = self.cnft_gate_receipt.as_mut().ok_or(CustomError::CnftGateRequired)?;
+            require!(!receipt.used, CustomError::CnftGateAlreadyUsed);
+            receipt.used = true;
+            receipt.bump = bumps.cnft_gate_receipt.unwrap();
+        }
+
+        Ok(())
+    }
+
+    pub fn claim_allow_list(&mut self, allow_list_proof: Option<AllowListProof>, bumps: &MintNFTBumps) -> Result<()> {
+        // If there is no Allow List entry, the user is not allowed
+        let allow_list_entry = self.allow_list_entry.as_mut().ok_or(CustomError::UserNotAllowed)?;
+
+        // If a proof is provided, verify the user and allotted amount against the allow list merkle root. Proofs only create or update
+        // merkle entries: the allotted amount of entries added or updated by the authority is kept, and the proof is ignored
+        let merkle_entry = allow_list_entry.user == Pubkey::default() || allow_list_entry.merkle;
+        if let Some(allow_list_proof) = allow_list_proof.filter(|_| merkle_entry) {
+            require!(
+                self.config.verify_allow_list_proof(self.user.key, allow_list_proof.amount, &allow_list_proof.proof), 
+                CustomError::InvalidAllowListProof
+            );
+            allow_list_entry.user = self.user.key();
+            allow_list_entry.allotted = allow_list_proof.amount;
+            allow_list_entry.merkle = true;
+            allow_list_entry.bump = bumps.allow_list_entry.unwrap();
+        }
+
+        // Check if the user is in the Allow List
+        require!(allow_list_entry.allotted > 0, CustomError::UserNotAllowed);
+
+        // Check if the user has already claimed the allotted amount
+        require!(allow_list_entry.claimed < allow_list_entry.allotted, CustomError::AlreadyClaimed);
+
+        // Increase the claimed amount of the user
+        allow_list_entry.claimed += 1;
 
         Ok(())
     }
 
-    pub fn close_account(&mut self) -> Result<()> {
-        // Close the config account and transfer the rent lamports to the authority
-        **self.authority.lamports.borrow_mut() = self.authority.lamports().checked_add(self.config.to_account_info().lamports()).unwrap();
-        **self.config.to_account_info().lamports.borrow_mut() = 0;
-        
+    pub fn transfer_sol(&mut self, amount: u64, recipient_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Split the payment between the recipients, or send it to the treasury if there are no recipients. 
+        // Recipient wallets must already be rent exempt, otherwise a share too small to fund them fails the mint
+        let destinations = match self.config.recipients.is_empty() {
+            true => vec![(self.treasury.to_account_info(), amount)],
+            false => {
+                require!(recipient_accounts.len() == self.config.recipients.len(), CustomError::InvalidRemainingAccounts);
+                self.config.split_payment(amount)
+                    .into_iter()
+                    .zip(recipient_accounts)
+                    .map(|((address, share), account)| {
+                        require_keys_eq!(account.key(), address, CustomError::InvalidRecipientAccount);
+                        Ok((account.clone(), share))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+            },
+        };
+
+        // Transfer the SOL to each destination
+        for (destination, share) in destinations {
+            let cpi_program = self.system_program.to_account_info();
+
+            let cpi_accounts = Transfer {
+                from: self.user.to_account_info(),
+                to: destination,
+            };
+
+            let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+
+            transfer(cpi_context, share)?;
+        }
+
         Ok(())
     }
-}
\ No newline at end of file
+
+    pub fn record_auction_payment(&mut self, paid: u64, base_paid: u64, bumps: &MintNFTBumps) -> Result<()> {
+        let Some(dutch_auction) = self.config.dutch_auction.as_mut().filter(|dutch_auction| dutch_auction.rebate) else {
+            return Ok(());
+        };
+
+        // Record the SOL paid and the price before the decay in the user auction receipt, so that the difference
+        // to the clearing price can be claimed back once the auction settles
+        let receipt = self.auction_receipt.as_mut().ok_or(CustomError::AuctionReceiptRequired)?;
+        if receipt.paid == 0 {
+            dutch_auction.receipts += 1;
+        }
+        receipt.paid += paid;
+        receipt.base_paid += base_paid;
+        receipt.bump = bumps.auction_receipt.unwrap();
+
+        dutch_auction.total_paid += paid;
+        dutch_auction.total_base_paid += base_paid;
+
+        Ok(())
+    }
+
+    pub fn record_goal_payment(&mut self, paid: u64, bumps: &MintNFTBumps) -> Result<()> {
+        let Some(sale_goal) = self.config.sale_goal.as_mut() else {
+            return Ok(());
+        };
+
+        // Record the SOL paid and the cNFTs bought in the user goal receipt, so that they can be refunded if the goal is not met by the deadline
+        let receipt = self.goal_receipt.as_mut().ok_or(CustomError::GoalReceiptRequired)?;
+        receipt.paid += paid;
+        receipt.minted += 1;
+        receipt.bump = bumps.goal_receipt.unwrap();
+
+        sale_goal.raised += paid;
+
+        Ok(())
+    }
+
+    pub fn transfer_spl(&mut self, currency: Pubkey, amount: u64, max_price: Option<u64>, recipient_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Check if the SPL payment accounts are provided
+        let (Some(mint), Some(source), Some(token_program)) = (&self.payment_mint, &self.payment_source, &self.payment_token_program) else {
+            return Err(CustomError::PaymentAccountsRequired.into());
+        };
+
+        // Check if the payment mint is the currency selected by the user
+        require_keys_eq!(mint.key(), currency, CustomError::InvalidPaymentMint);
+
+        // Check if the source account is the user ATA. The mint can be a Token or Token-2022 mint
+        let expected_source = get_associated_token_address_with_program_id(self.user.key, &mint.key(), token_program.key);
+        require_keys_eq!(source.key(), expected_source, CustomError::InvalidPaymentSource);
+
+        let Some(associated_token_program) = &self.associated_token_program else {
+            return Err(CustomError::PaymentAccountsRequired.into());
+        };
+
+        // Split the payment between the recipient ATAs, or send it to the treasury ATA if there are no recipients
+        let destinations = match self.config.recipients.is_empty() {
+            true => {
+                let Some(destination) = &self.payment_destination else {
+                    return Err(CustomError::PaymentAccountsRequired.into());
+                };
+                let expected_destination = get_associated_token_address_with_program_id(self.treasury.key, &mint.key(), token_program.key);
+                require_keys_eq!(destination.key(), expected_destination, CustomError::InvalidPaymentDestination);
+
+                vec![(self.treasury.to_account_info(), destination.to_account_info(), amount)]
+            },
+            false => {
+                require!(recipient_accounts.len() == self.config.recipients.len() * 2, CustomError::InvalidRemainingAccounts);
+                self.config.split_payment(amount)
+                    .into_iter()
+                    .zip(recipient_accounts.chunks(2))
+                    .map(|((address, share), accounts)| {
+                        let (wallet, destination) = (&accounts[0], &accounts[1]);
+                        require_keys_eq!(wallet.key(), address, CustomError::InvalidRecipientAccount);
+                        let expected_destination = get_associated_token_address_with_program_id(&address, &mint.key(), token_program.key);
+                        require_keys_eq!(destination.key(), expected_destination, CustomError::InvalidRecipientAccount);
+                        Ok((wallet.clone(), destination.clone(), share))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+            },
+        };
+
+        // The amount of each transfer covers any transfer fee, so each destination receives its full share. The max price
+        // the user accepts is checked against the total amount debited from the user
+        let destinations = destinations
+            .into_iter()
+            .map(|(owner, destination, share)| Ok((owner, destination, payment_gross_amount(&mint.to_account_info(), share)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let total_amount = destinations.iter().try_fold(0u64, |total, (_, _, gross_amount)| total.checked_add(*gross_amount));
+        require!(total_amount.ok_or(CustomError::InvalidPaymentAmount)? <= max_price.unwrap_or(u64::MAX), CustomError::PriceExceedsMax);
+
+        // Transfer the SPL tokens to each destination, creating the destination ATA if it does not exist yet
+        for (owner, destination, gross_amount) in destinations {
+            let cpi_program = associated_token_program.to_account_info();
+            let cpi_accounts = Create {
+                payer: self.user.to_account_info(),
+                associated_token: destination.to_account_info(),
+                authority: owner,
+                mint: mint.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                token_program: token_program.to_account_info(),
+            };
+            create_idempotent(CpiContext::new(cpi_program, cpi_accounts))?;
+
+            let cpi_program = token_program.to_account_info();
+            let cpi_accounts = TransferChecked {
+                from: source.to_account_info(),
+                mint: mint.to_account_info(),
+                to: destination,
+                authority: self.user.to_account_info(),
+            };
+            let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+            transfer_checked(cpi_context, gross_amount, mint.decimals)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_bot_taxable(error: &Error) -> bool {
+    // Only the failed gate checks of the mint are charged with the bot tax
+    let Error::AnchorError(error) = error else {
+        return false;
+    };
+
+    [
+        CustomError::CandyMachineInactive,
+        CustomError::SaleNotStarted,
+        CustomError::SaleEnded,
+        CustomError::NoActivePhase,
+        CustomError::PhaseSoldOut,
+        CustomError::UserNotAllowed,
+        CustomError::AlreadyClaimed,
+        CustomError::InvalidAllowListProof,
+        CustomError::InvalidAllowMint,
+        CustomError::InvalidAllowMintATA,
+        CustomError::MintLimitReached,
+        CustomError::GatekeeperRequired,
+        CustomError::InvalidGatekeeperSignature,
+        CustomError::GatekeeperSignatureExpired,
+        CustomError::GatekeeperNonceUsed,
+        CustomError::TokenGateRequired,
+        CustomError::InvalidTokenGateAccount,
+        CustomError::NotEnoughTokens,
+        CustomError::TokenGateAlreadyClaimed,
+        CustomError::NftGateRequired,
+        CustomError::InvalidNftGateAccount,
+        CustomError::InvalidNftCollection,
+        CustomError::NftGateAlreadyUsed,
+        CustomError::CnftGateRequired,
+        CustomError::InvalidCnftGateTree,
+        CustomError::CnftGateAlreadyUsed,
+    ]
+    .into_iter()
+    .any(|taxable| error.error_code_number == u32::from(taxable))
+}
+
+fn parse_ed25519_instruction(data: &[u8]) -> Option<(Pubkey, &[u8])> {
+    // Ed25519 Program instruction data: number of signatures (u8), padding (u8), then the signature offsets.
+    // Only a single signature whose public key and message are in this same instruction is accepted
+    if data.len() < 16 || data[0] != 1 {
+        return None;
+    }
+    let read_u16 = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+
+    let signature_instruction_index = read_u16(4);
+    let public_key_offset = read_u16(6) as usize;
+    let public_key_instruction_index = read_u16(8);
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+    let message_instruction_index = read_u16(14);
+
+    if [signature_instruction_index, public_key_instruction_index, message_instruction_index].iter().any(|index| *index != u16::MAX) {
+        return None;
+    }
+
+    let public_key = Pubkey::try_from(data.get(public_key_offset..public_key_offset + 32)?).ok()?;
+    let message = data.get(message_offset..message_offset + message_size)?;
+    Some((public_key, message))
+}
@@ -1,11 +1,65 @@
 pub mod initialize;
 pub mod set_tree_status;
+pub mod set_sale_window;
+pub mod set_phases;
+pub mod set_payment_mints;
+pub mod set_recipients;
+pub mod set_max_per_wallet;
+pub mod set_bot_tax;
+pub mod set_gatekeeper;
+pub mod set_token_gate;
+pub mod set_nft_gate;
+pub mod set_cnft_gate;
+pub mod set_dutch_auction;
+pub mod set_bonding_curve;
+pub mod set_gate_discounts;
+pub mod set_usd_price;
+pub mod set_sale_goal;
 pub mod allow_list;
+pub mod allow_list_batch;
+pub mod update_allow_list;
+pub mod remove_allow_list;
+pub mod set_allow_list_root;
 pub mod create_collection;
+pub mod initialize_config_lines;
+pub mod add_config_lines;
 pub mod mint;
+pub mod reveal;
+pub mod withdraw;
+pub mod claim_rebate;
+pub mod refund;
+pub mod release_token_gate;
+pub mod close;
 
 pub use initialize::*;
 pub use set_tree_status::*;
+pub use set_sale_window::*;
+pub use set_phases::*;
+pub use set_payment_mints::*;
+pub use set_recipients::*;
+pub use set_max_per_wallet::*;
+pub use set_bot_tax::*;
+pub use set_gatekeeper::*;
+pub use set_token_gate::*;
+pub use set_nft_gate::*;
+pub use set_cnft_gate::*;
+pub use set_dutch_auction::*;
+pub use set_bonding_curve::*;
+pub use set_gate_discounts::*;
+pub use set_usd_price::*;
+pub use set_sale_goal::*;
 pub use allow_list::*;
+pub use allow_list_batch::*;
+pub use update_allow_list::*;
+pub use remove_allow_list::*;
+pub use set_allow_list_root::*;
 pub use create_collection::*;
+pub use initialize_config_lines::*;
+pub use add_config_lines::*;
 pub use mint::*;
+pub use reveal::*;
+pub use withdraw::*;
+pub use claim_rebate::*;
+pub use refund::*;
+pub use release_token_gate::*;
+pub use close::*;
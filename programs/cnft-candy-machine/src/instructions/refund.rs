@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{
+    transfer, 
+    Transfer
+};
+use mpl_bubblegum::accounts::TreeConfig;
+use mpl_bubblegum::instructions::BurnCpiBuilder;
+use mpl_bubblegum::ID as BUBBLEGUM_ID;
+use spl_account_compression::ID as SPL_ACCOUNT_COMPRESSION_ID;
+use spl_noop::ID as SPL_NOOP_ID;
+
+use crate::{
+    state::{
+        Config, 
+        GoalReceipt, 
+        RefundLeaf
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct Refund<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub authority: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"treasury", config.key().as_ref()],
+        bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"goal", config.key().as_ref(), user.key().as_ref()],
+        bump = goal_receipt.bump,
+    )]
+    pub goal_receipt: Account<'info, GoalReceipt>,
+    /// CHECK: Tree Config account of the candy machine tree, checked against the config and by the Bubblegum Program
+    #[account(mut)]
+    pub tree_config: UncheckedAccount<'info>,
+    /// CHECK: Merkle Tree account that will be checked by the Bubblegum Program
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    /// CHECK: SPL NOOP Program checked by the corresponding address
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: Bubblegum Program checked by the corresponding address
+    #[account(address = BUBBLEGUM_ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    /// CHECK: SPL Account Compression Program checked by the corresponding address
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Refund<'info> {
+    pub fn refund(&mut self, leaves: Vec<RefundLeaf>, bumps: &RefundBumps, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Check if the sale goal was not met by the deadline
+        let sale_goal = self.config.sale_goal.ok_or(CustomError::SaleGoalNotFailed)?;
+        require!(sale_goal.is_failed(Clock::get()?.unix_timestamp), CustomError::SaleGoalNotFailed);
+
+        // Check if the tree is the one of the candy machine, whose tree config was created by the config at initialize
+        require_keys_eq!(self.tree_config.key(), TreeConfig::find_pda(self.merkle_tree.key).0, CustomError::InvalidRefundLeaves);
+        require_keys_eq!(*self.tree_config.owner, BUBBLEGUM_ID, CustomError::InvalidRefundLeaves);
+        let tree_config = TreeConfig::from_bytes(&self.tree_config.try_borrow_data()?)?;
+        require_keys_eq!(tree_config.tree_creator, self.config.key(), CustomError::InvalidRefundLeaves);
+
+        // Each cNFT bought can be refunded once. The remaining accounts hold the proof of each leaf, all of the same length
+        let receipt = &self.goal_receipt;
+        require!(!leaves.is_empty() && leaves.len() <= receipt.minted as usize, CustomError::InvalidRefundLeaves);
+        let proof_length = remaining_accounts.len() / leaves.len();
+        require!(proof_length * leaves.len() == remaining_accounts.len(), CustomError::InvalidRefundLeaves);
+
+        // Burn the cNFTs of the candy machine owned by the user, so that they are not kept once refunded
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = remaining_accounts[index * proof_length..(index + 1) * proof_length]
+                .iter()
+                .map(|account| (account, false, false))
+                .collect::<Vec<_>>();
+
+            BurnCpiBuilder::new(&self.bubblegum_program.to_account_info())
+                .tree_config(&self.tree_config.to_account_info())
+                .leaf_owner(&self.user.to_account_info(), true)
+                .leaf_delegate(&self.user.to_account_info(), false)
+                .merkle_tree(&self.merkle_tree.to_account_info())
+                .log_wrapper(&self.log_wrapper.to_account_info())
+                .compression_program(&self.compression_program.to_account_info())
+                .system_program(&self.system_program.to_account_info())
+                .root(leaf.root)
+                .data_hash(leaf.data_hash)
+                .creator_hash(leaf.creator_hash)
+                .nonce(leaf.nonce)
+                .index(leaf.index)
+                .add_remaining_accounts(&proof)
+                .invoke()?;
+        }
+
+        // The user gets back the average price paid for each burned cNFT, and everything left once the last one is burned
+        let refund = match leaves.len() == receipt.minted as usize {
+            true => receipt.paid,
+            false => (receipt.paid as u128 * leaves.len() as u128 / receipt.minted as u128) as u64,
+        };
+
+        // The treasury holds the escrowed payments on top of the rent exemption funded at initialize, and has to keep it
+        let rent = Rent::get()?.minimum_balance(0);
+        require!(self.treasury.lamports().saturating_sub(refund) >= rent, CustomError::InsufficientTreasuryFunds);
+
+        // Record the refund, so the authority can withdraw what is left in the treasury once every buyer is refunded
+        if let Some(sale_goal) = self.config.sale_goal.as_mut() {
+            sale_goal.refunded += refund;
+        }
+
+        // Create signer seeds for the treasury
+        let config_key = self.config.key();
+        let seeds = &[
+            &b"treasury"[..], 
+            config_key.as_ref(),
+            &[bumps.treasury],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        // Transfer the refund back from the treasury
+        let cpi_program = self.system_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: self.treasury.to_account_info(),
+            to: self.user.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        transfer(cpi_context, refund)?;
+
+        // The receipt keeps the cNFTs not refunded yet, and is closed once every cNFT is refunded
+        self.goal_receipt.paid -= refund;
+        self.goal_receipt.minted -= leaves.len() as u32;
+        if self.goal_receipt.minted == 0 {
+            self.goal_receipt.close(self.user.to_account_info())?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    close_account, 
+    transfer_checked, 
+    CloseAccount, 
+    Mint, 
+    TokenAccount, 
+    TokenInterface, 
+    TransferChecked
+};
+
+use crate::{
+    state::{
+        Config, 
+        GateReceipt
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct ReleaseTokenGate<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub authority: SystemAccount<'info>,
+    /// CHECK: Config checked by the seeds. It is only read if it was not closed
+    #[account(
+        seeds = [b"config", authority.key().as_ref()],
+        bump,
+    )]
+    pub config: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"token_gate", config.key().as_ref(), user.key().as_ref()],
+        bump = token_gate_receipt.bump,
+    )]
+    pub token_gate_receipt: Account<'info, GateReceipt>,
+    pub token_gate_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = token_gate_mint,
+        associated_token::authority = token_gate_receipt,
+        associated_token::token_program = token_program,
+    )]
+    pub token_gate_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = token_gate_mint,
+        token::authority = user,
+        token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ReleaseTokenGate<'info> {
+    pub fn release_token_gate(&mut self) -> Result<()> {
+        // Check if the sale is over: the config was closed, the collection sold out, the sale window ended,
+        // or the token gate no longer locks the tokens
+        if !self.config.data_is_empty() {
+            let config = Config::try_deserialize(&mut &self.config.try_borrow_data()?[..])?;
+            let now = Clock::get()?.unix_timestamp;
+            let sold_out = config.current_supply >= config.total_supply;
+            let ended = config.end_time.is_some_and(|end_time| now >= end_time);
+            let locked = config.token_gate.is_some_and(|token_gate| token_gate.single_use);
+            require!(sold_out || ended || !locked, CustomError::TokenGateLocked);
+        }
+
+        // Create signer seeds for the token gate receipt
+        let config_key = self.config.key();
+        let user_key = self.user.key();
+        let seeds = &[
+            &b"token_gate"[..], 
+            config_key.as_ref(),
+            user_key.as_ref(),
+            &[self.token_gate_receipt.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        // Transfer the locked tokens back to the user
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = TransferChecked {
+            from: self.token_gate_escrow.to_account_info(),
+            mint: self.token_gate_mint.to_account_info(),
+            to: self.user_token_account.to_account_info(),
+            authority: self.token_gate_receipt.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        transfer_checked(cpi_context, self.token_gate_escrow.amount, self.token_gate_mint.decimals)?;
+
+        // Close the escrow and return its rent to the user. The receipt is closed afterwards
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = CloseAccount {
+            account: self.token_gate_escrow.to_account_info(),
+            destination: self.user.to_account_info(),
+            authority: self.token_gate_receipt.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        close_account(cpi_context)
+    }
+}
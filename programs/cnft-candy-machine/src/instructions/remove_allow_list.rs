@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{
+        AllowListEntry, 
+        Config
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct RemoveAllowList<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"allow", config.key().as_ref(), user.as_ref()],
+        bump = allow_list_entry.bump,
+    )]
+    pub allow_list_entry: Account<'info, AllowListEntry>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RemoveAllowList<'info> {
+    pub fn remove(&mut self, _user: Pubkey) -> Result<()> {
+        // Entries created from a merkle proof are recreated on the next mint, so removing a used one would reset its claimed mints.
+        // Entries added by the authority are not in the merkle tree and can always be removed
+        require!(
+            !self.allow_list_entry.merkle || self.config.allow_list_root.is_none() || self.allow_list_entry.claimed == 0,
+            CustomError::AllowListEntryClaimed
+        );
+
+        // The allow list entry is closed by Anchor and its rent is refunded to the authority
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    metadata::{
+        Metadata, 
+        MetadataAccount
+    }, 
+    token::Mint
+};
+use mpl_bubblegum::{
+    instructions::UpdateMetadataCpiBuilder, 
+    types::UpdateArgs, 
+    ID as BUBBLEGUM_ID
+};
+use spl_account_compression::ID as SPL_ACCOUNT_COMPRESSION_ID;
+use spl_noop::ID as SPL_NOOP_ID;
+
+use crate::{
+    state::{
+        Config, 
+        ConfigLines
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct Reveal<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        seeds = [b"config_lines", config.key().as_ref()],
+        bump = config_lines.bump,
+    )]
+    pub config_lines: Account<'info, ConfigLines>,
+    #[account(
+        seeds = [b"collection", config.key().as_ref()],
+        bump,
+    )]
+    pub collection: Account<'info, Mint>,
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            collection.key().as_ref()
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub collection_metadata: Account<'info, MetadataAccount>,
+    /// CHECK: Leaf owner of the cNFT that will be checked by the Bubblegum Program
+    pub leaf_owner: UncheckedAccount<'info>,
+    /// CHECK: Leaf delegate of the cNFT that will be checked by the Bubblegum Program
+    pub leaf_delegate: UncheckedAccount<'info>,
+    /// CHECK: Tree Config account that will be checked by the Bubblegum Program
+    pub tree_config: UncheckedAccount<'info>,
+    /// CHECK: Merkle Tree account that will be checked by the Bubblegum Program
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    /// CHECK: SPL NOOP Program checked by the corresponding address
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: Bubblegum Program checked by the corresponding address
+    #[account(address = BUBBLEGUM_ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    /// CHECK: SPL Account Compression Program checked by the corresponding address
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub metadata_program: Program<'info, Metadata>,
+}
+
+impl<'info> Reveal<'info> {
+    pub fn reveal(&mut self, root: [u8; 32], nonce: u64, index: u32, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Check if the config has hidden settings and get the placeholder the cNFT was minted with
+        let hidden_settings = self.config.hidden_settings.clone().ok_or(CustomError::HiddenSettingsNotEnabled)?;
+
+        // Check if all the config lines have been loaded
+        require!(self.config_lines.line_count == self.config.total_supply, CustomError::ConfigLinesNotLoaded);
+
+        // cNFTs are minted in order to the tree, so the leaf index is also the index of the config line to reveal
+        let line = ConfigLines::read_line(&self.config_lines.to_account_info(), index)?;
+
+        // Rebuild the metadata the cNFT was minted with, so the Bubblegum Program can verify the leaf
+        let symbol = self.collection_metadata.symbol.trim_matches(char::from(0)).to_string();
+        let current_metadata = self.config.metadata_args(hidden_settings.name, symbol, hidden_settings.uri);
+
+        // Create signer seeds for the CPI calls
+        let seeds = &[
+            &b"config"[..], 
+            self.authority.key.as_ref(),
+            &[self.config.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        // Proof accounts for the leaf
+        let proof = remaining_accounts
+            .iter()
+            .map(|account| (account, false, false))
+            .collect::<Vec<_>>();
+
+        // CPI call to the Bubblegum Program to update the cNFT metadata, using the config as collection authority
+        UpdateMetadataCpiBuilder::new(&self.bubblegum_program.to_account_info())
+            .tree_config(&self.tree_config.to_account_info())
+            .authority(&self.config.to_account_info())
+            .collection_mint(Some(&self.collection.to_account_info()))
+            .collection_metadata(Some(&self.collection_metadata.to_account_info()))
+            .collection_authority_record_pda(None)
+            .leaf_owner(&self.leaf_owner.to_account_info())
+            .leaf_delegate(&self.leaf_delegate.to_account_info())
+            .payer(&self.authority.to_account_info())
+            .merkle_tree(&self.merkle_tree.to_account_info())
+            .log_wrapper(&self.log_wrapper.to_account_info())
+            .compression_program(&self.compression_program.to_account_info())
+            .token_metadata_program(&self.metadata_program.to_account_info())
+            .system_program(&self.system_program.to_account_info())
+            .root(root)
+            .nonce(nonce)
+            .index(index)
+            .current_metadata(current_metadata)
+            .update_args(UpdateArgs {
+                name: Some(line.name),
+                uri: Some(line.uri),
+                ..UpdateArgs::default()
+            })
+            .add_remaining_accounts(&proof)
+            .invoke_signed(signer_seeds)?;
+
+        Ok(())
+    }
+}
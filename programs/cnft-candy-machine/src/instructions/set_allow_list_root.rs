@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Config;
+
+#[derive(Accounts)]
+pub struct SetAllowListRoot<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetAllowListRoot<'info> {
+    pub fn set_allow_list_root(&mut self, root: Option<[u8; 32]>) -> Result<()> {
+        // Set the merkle root of the allow list. Users prove their allotted amount against it when minting
+        self.config.allow_list_root = root;
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::MAX_CURVE_POINTS, 
+    state::{
+        BondingCurve, 
+        Config
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct SetBondingCurve<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetBondingCurve<'info> {
+    pub fn set_bonding_curve(&mut self, bonding_curve: Option<BondingCurve>) -> Result<()> {
+        match &bonding_curve {
+            // Check if each step has at least one cNFT
+            Some(BondingCurve::ExponentialStep { step_size, .. }) => require!(*step_size > 0, CustomError::InvalidBondingCurve),
+            // Check if the points fit in the config and are ordered by supply
+            Some(BondingCurve::Piecewise { points }) => {
+                require!(!points.is_empty() && points.len() <= MAX_CURVE_POINTS, CustomError::InvalidBondingCurve);
+                require!(points.windows(2).all(|pair| pair[0].supply < pair[1].supply), CustomError::InvalidBondingCurve);
+            },
+            _ => {},
+        }
+
+        // Set the curve the prices follow as the current supply grows. Without it, the configured prices are used
+        self.config.bonding_curve = bonding_curve;
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Config;
+
+#[derive(Accounts)]
+pub struct SetBotTax<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetBotTax<'info> {
+    pub fn set_bot_tax(&mut self, bot_tax: Option<u64>) -> Result<()> {
+        // Set the lamports charged to the user instead of failing when a gate check of the mint fails
+        self.config.bot_tax = bot_tax;
+        Ok(())
+    }
+}
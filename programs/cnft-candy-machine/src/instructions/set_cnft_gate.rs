@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{
+    Config, 
+    CnftGate
+};
+
+#[derive(Accounts)]
+pub struct SetCnftGate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetCnftGate<'info> {
+    pub fn set_cnft_gate(&mut self, cnft_gate: Option<CnftGate>) -> Result<()> {
+        // Set the merkle tree of the cNFT the user must hold to mint with the cNFT holder gate
+        self.config.cnft_gate = cnft_gate;
+        Ok(())
+    }
+}
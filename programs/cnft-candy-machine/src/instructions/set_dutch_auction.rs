@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::BASIS_POINTS, 
+    state::{
+        Config, 
+        DutchAuction, 
+        DutchAuctionParams
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct SetDutchAuction<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetDutchAuction<'info> {
+    pub fn set_dutch_auction(&mut self, dutch_auction: Option<DutchAuctionParams>) -> Result<()> {
+        // The rebates owed to the buyers depend on the auction schedule, so a rebate auction with sales cannot change until it settles.
+        // Once settled, the rebates not claimed yet are kept in the treasury as pending rebates, claimed at the same clearing price
+        if let Some(current) = self.config.dutch_auction.filter(|current| current.rebate && current.receipts > 0) {
+            let sold_out = self.config.current_supply >= self.config.total_supply;
+            require!(current.is_settled(Clock::get()?.unix_timestamp, sold_out), CustomError::DutchAuctionLocked);
+            self.config.pending_rebates = Some(current.pending_rebates(sold_out));
+        }
+
+        let dutch_auction = match dutch_auction {
+            Some(dutch_auction) => {
+                // Check if the schedule ends after it starts and the floor is not above the full price
+                require!(dutch_auction.start_time < dutch_auction.end_time, CustomError::InvalidDutchAuction);
+                require!(dutch_auction.floor_bps <= BASIS_POINTS, CustomError::InvalidDutchAuction);
+
+                // Auction receipts are kept per user, so a new rebate auction can only start once the pending rebates are claimed
+                require!(!dutch_auction.rebate || self.config.pending_rebates.is_none(), CustomError::DutchAuctionLocked);
+
+                // Check if the treasury can pay the rebates, see `Config::treasury_payouts_compatible`
+                require!(
+                    Config::treasury_payouts_compatible(
+                        !self.config.recipients.is_empty(), 
+                        dutch_auction.rebate || self.config.pending_rebates.is_some(), 
+                        self.config.sale_goal.is_some()
+                    ), 
+                    CustomError::InvalidDutchAuction
+                );
+
+                // Start the auction without sales
+                Some(DutchAuction {
+                    start_time: dutch_auction.start_time,
+                    end_time: dutch_auction.end_time,
+                    floor_bps: dutch_auction.floor_bps,
+                    step_interval: dutch_auction.step_interval,
+                    rebate: dutch_auction.rebate,
+                    clearing_bps: BASIS_POINTS,
+                    total_paid: 0,
+                    total_base_paid: 0,
+                    rebated: 0,
+                    receipts: 0,
+                })
+            },
+            None => None,
+        };
+
+        // Set the schedule of the decaying price. Without it, the configured prices are used
+        self.config.dutch_auction = dutch_auction;
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::{
+        BASIS_POINTS, 
+        MAX_GATE_DISCOUNTS
+    }, 
+    state::{
+        Config, 
+        GateDiscount
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct SetGateDiscounts<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetGateDiscounts<'info> {
+    pub fn set_gate_discounts(&mut self, gate_discounts: Vec<GateDiscount>) -> Result<()> {
+        // Check if the discounts fit in the config
+        require!(gate_discounts.len() <= MAX_GATE_DISCOUNTS, CustomError::InvalidDiscount);
+
+        // Check if each discount is not above the full price and each gate has a single discount
+        for (index, gate_discount) in gate_discounts.iter().enumerate() {
+            require!(gate_discount.discount_bps <= BASIS_POINTS, CustomError::InvalidDiscount);
+            require!(gate_discounts[..index].iter().all(|previous| previous.gate != gate_discount.gate), CustomError::InvalidDiscount);
+        }
+
+        // Set the discount on the price of the mints that pass each gate
+        self.config.gate_discounts = gate_discounts;
+        Ok(())
+    }
+}
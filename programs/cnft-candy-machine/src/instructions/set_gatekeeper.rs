@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{
+    Config, 
+    Gatekeeper
+};
+
+#[derive(Accounts)]
+pub struct SetGatekeeper<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetGatekeeper<'info> {
+    pub fn set_gatekeeper(&mut self, gatekeeper: Option<Gatekeeper>) -> Result<()> {
+        // Set the gatekeeper whose signature is required to mint
+        self.config.gatekeeper = gatekeeper;
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Config;
+
+#[derive(Accounts)]
+pub struct SetMaxPerWallet<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetMaxPerWallet<'info> {
+    pub fn set_max_per_wallet(&mut self, max_per_wallet: Option<u32>) -> Result<()> {
+        // Set the maximum amount of cNFTs each wallet can mint through the public gate
+        self.config.max_per_wallet = max_per_wallet;
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{
+    Config, 
+    NftGate
+};
+
+#[derive(Accounts)]
+pub struct SetNftGate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetNftGate<'info> {
+    pub fn set_nft_gate(&mut self, nft_gate: Option<NftGate>) -> Result<()> {
+        // Set the collection of the NFT the user must hold to mint with the NFT holder gate
+        self.config.nft_gate = nft_gate;
+        Ok(())
+    }
+}
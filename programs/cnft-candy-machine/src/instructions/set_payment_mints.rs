@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::MAX_PAYMENT_MINTS, 
+    state::{
+        Config, 
+        PaymentMint
+    }, 
+    utils::check_payment_mint, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct SetPaymentMints<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetPaymentMints<'info> {
+    pub fn set_payment_mints(&mut self, payment_mints: Vec<PaymentMint>, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Check if the payment mints fit in the config
+        require!(payment_mints.len() <= MAX_PAYMENT_MINTS, CustomError::InvalidPaymentMints);
+
+        // The mint account of each payment mint is passed as remaining accounts in the same order
+        require!(remaining_accounts.len() == payment_mints.len(), CustomError::InvalidRemainingAccounts);
+
+        for (index, (payment_mint, mint)) in payment_mints.iter().zip(remaining_accounts).enumerate() {
+            // Check if the account is the Token or Token-2022 mint of the payment mint, and its extensions allow transfers
+            require_keys_eq!(mint.key(), payment_mint.mint, CustomError::InvalidPaymentMint);
+            check_payment_mint(mint)?;
+
+            // Check if the payment mint is not repeated
+            require!(payment_mints[..index].iter().all(|previous| previous.mint != payment_mint.mint), CustomError::InvalidPaymentMints);
+        }
+
+        // Set the SPL tokens accepted as payment, each with its own price
+        self.config.payment_mints = payment_mints;
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::MAX_PAYMENT_MINTS, 
+    state::{
+        Config, 
+        Phase, 
+        PhaseParams
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+#[instruction(phases: Vec<PhaseParams>)]
+pub struct SetPhases<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+        realloc = Config::INIT_SPACE + (phases.len() * Phase::INIT_SPACE),
+        realloc::payer = authority,
+        realloc::zero = true,
+    )]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetPhases<'info> {
+    pub fn set_phases(&mut self, phases: Vec<PhaseParams>) -> Result<()> {
+        // Check if each phase window is valid and starts after the previous phase ends
+        let mut previous_end_time = i64::MIN;
+        for phase in phases.iter() {
+            require!(phase.start_time < phase.end_time, CustomError::InvalidPhases);
+            require!(phase.start_time >= previous_end_time, CustomError::InvalidPhases);
+            previous_end_time = phase.end_time;
+
+            // Check if the phase only overrides the price of accepted payment mints
+            require!(phase.payment_mints.len() <= MAX_PAYMENT_MINTS, CustomError::InvalidPhases);
+            require!(
+                phase.payment_mints.iter().all(|phase_payment_mint| self.config.payment_mints.iter().any(|payment_mint| payment_mint.mint == phase_payment_mint.mint)),
+                CustomError::InvalidPhases
+            );
+        }
+
+        // Keep the minted amount of the phases already configured at the same position, so supply caps cannot be reset
+        let phases = phases
+            .into_iter()
+            .enumerate()
+            .map(|(index, phase)| Phase {
+                start_time: phase.start_time,
+                end_time: phase.end_time,
+                price_sol: phase.price_sol,
+                payment_mints: phase.payment_mints,
+                gate: phase.gate,
+                supply_cap: phase.supply_cap,
+                minted: self.config.phases.get(index).map_or(0, |current| current.minted),
+            })
+            .collect();
+
+        // Set the phases. When phases are configured, the active phase defines the gate and prices of each mint
+        self.config.phases = phases;
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::{
+        BASIS_POINTS, 
+        MAX_RECIPIENTS
+    }, 
+    state::{
+        Config, 
+        Recipient
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct SetRecipients<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetRecipients<'info> {
+    pub fn set_recipients(&mut self, recipients: Vec<Recipient>) -> Result<()> {
+        // Check if the recipients fit in the config. Without recipients, every payment goes to the treasury
+        require!(recipients.len() <= MAX_RECIPIENTS, CustomError::InvalidRecipients);
+
+        if !recipients.is_empty() {
+            // Check if the SOL payments can be shared, see `Config::treasury_payouts_compatible`
+            require!(
+                Config::treasury_payouts_compatible(true, self.config.has_rebates(), self.config.sale_goal.is_some()), 
+                CustomError::InvalidRecipients
+            );
+
+            // Check if each recipient has a share and is not repeated
+            for (index, recipient) in recipients.iter().enumerate() {
+                require!(recipient.bps > 0, CustomError::InvalidRecipients);
+                require!(recipients[..index].iter().all(|previous| previous.address != recipient.address), CustomError::InvalidRecipients);
+            }
+
+            // Check if the shares add up to the whole payment
+            let total_bps = recipients.iter().map(|recipient| recipient.bps as u32).sum::<u32>();
+            require!(total_bps == BASIS_POINTS as u32, CustomError::InvalidRecipients);
+        }
+
+        // Set the recipients that share the payment of each mint. The SOL shares are sent to the recipient wallets as they are, 
+        // so each wallet must already hold the rent exemption of an empty account, or a SOL mint with a small share fails
+        self.config.recipients = recipients;
+        Ok(())
+    }
+}
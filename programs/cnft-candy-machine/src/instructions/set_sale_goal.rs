@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{
+        Config, 
+        SaleGoal, 
+        SaleGoalParams
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct SetSaleGoal<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetSaleGoal<'info> {
+    pub fn set_sale_goal(&mut self, sale_goal: Option<SaleGoalParams>) -> Result<()> {
+        // Buyers may be owed refunds, so the goal cannot change once it has sales
+        if let Some(current) = &self.config.sale_goal {
+            require!(current.raised == 0, CustomError::SaleGoalLocked);
+        }
+
+        let sale_goal = match sale_goal {
+            Some(sale_goal) => {
+                // Check if there is an amount to raise
+                require!(sale_goal.goal > 0, CustomError::InvalidSaleGoal);
+
+                // Check if the treasury can pay the refunds, see `Config::treasury_payouts_compatible`
+                require!(
+                    Config::treasury_payouts_compatible(!self.config.recipients.is_empty(), self.config.has_rebates(), true), 
+                    CustomError::InvalidSaleGoal
+                );
+
+                // Start the goal with nothing raised
+                Some(SaleGoal {
+                    goal: sale_goal.goal,
+                    deadline: sale_goal.deadline,
+                    raised: 0,
+                    refunded: 0,
+                })
+            },
+            None => None,
+        };
+
+        // Set the minimum amount of lamports to raise by the deadline. Without it, the treasury can be withdrawn at any time
+        self.config.sale_goal = sale_goal;
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::Config, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct SetSaleWindow<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetSaleWindow<'info> {
+    pub fn set_sale_window(&mut self, start_time: Option<i64>, end_time: Option<i64>) -> Result<()> {
+        // Check if the sale ends after it starts
+        if let (Some(start_time), Some(end_time)) = (start_time, end_time) {
+            require!(start_time < end_time, CustomError::InvalidSaleWindow);
+        }
+
+        // Set the sale window. Minting is only possible between the start and end time, when set
+        self.config.start_time = start_time;
+        self.config.end_time = end_time;
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{
+    Config, 
+    TokenGate
+};
+
+#[derive(Accounts)]
+pub struct SetTokenGate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetTokenGate<'info> {
+    pub fn set_token_gate(&mut self, token_gate: Option<TokenGate>) -> Result<()> {
+        // Set the token the user must hold to mint with the token holder gate
+        self.config.token_gate = token_gate;
+        Ok(())
+    }
+}
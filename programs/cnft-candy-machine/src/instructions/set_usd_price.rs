@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::BASIS_POINTS, 
+    state::{
+        Config, 
+        UsdPrice
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct SetUsdPrice<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetUsdPrice<'info> {
+    pub fn set_usd_price(&mut self, usd_price: Option<UsdPrice>) -> Result<()> {
+        // Check if the staleness and confidence limits are valid
+        if let Some(usd_price) = &usd_price {
+            require!(usd_price.max_staleness >= 0, CustomError::InvalidUsdPrice);
+            require!(usd_price.max_confidence_bps <= BASIS_POINTS, CustomError::InvalidUsdPrice);
+        }
+
+        // Set the price in USD cents and the oracle used to convert it to SOL. Without it, the configured SOL price is used
+        self.config.usd_price = usd_price;
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::BASIS_POINTS, 
+    state::{
+        AllowListEntry, 
+        Config
+    }, 
+    CustomError
+};
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct UpdateAllowList<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"allow", config.key().as_ref(), user.as_ref()],
+        bump = allow_list_entry.bump,
+    )]
+    pub allow_list_entry: Account<'info, AllowListEntry>,
+}
+
+impl<'info> UpdateAllowList<'info> {
+    pub fn update(&mut self, _user: Pubkey, amount: u8, discount_bps: Option<u16>) -> Result<()> {
+        // Check if the discount is not above the full price
+        require!(discount_bps.unwrap_or(0) <= BASIS_POINTS, CustomError::InvalidDiscount);
+
+        // Update the allotted amount and discount of the user. The amount already claimed is kept, and the entry is now managed
+        // by the authority, so merkle proofs no longer change it
+        self.allow_list_entry.allotted = amount;
+        self.allow_list_entry.discount_bps = discount_bps;
+        self.allow_list_entry.merkle = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{
+    transfer, 
+    Transfer
+};
+use anchor_spl::token_interface::{
+    transfer_checked, 
+    Mint, 
+    TokenAccount, 
+    TokenInterface, 
+    TransferChecked
+};
+
+use crate::{
+    state::Config, 
+    utils::payment_gross_amount, 
+    CustomError
+};
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config", authority.key().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"treasury", config.key().as_ref()],
+        bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+    /// CHECK: Destination of the SOL chosen by the authority
+    #[account(mut)]
+    pub destination: Option<UncheckedAccount<'info>>,
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub destination_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Withdraw<'info> {
+    pub fn withdraw(&mut self, amount: Option<u64>, bumps: &WithdrawBumps) -> Result<()> {
+        // Create signer seeds for the treasury
+        let config_key = self.config.key();
+        let seeds = &[
+            &b"treasury"[..], 
+            config_key.as_ref(),
+            &[bumps.treasury],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        // If the SPL accounts are provided, withdraw the SPL tokens of the mint, otherwise withdraw SOL
+        match (&self.mint, &self.treasury_token_account, &self.destination_token_account, &self.token_program) {
+            (Some(mint), Some(treasury_token_account), Some(destination_token_account), Some(token_program)) => {
+                // Check if the treasury token account belongs to the treasury and holds the mint
+                require_keys_eq!(treasury_token_account.owner, self.treasury.key(), CustomError::InvalidTreasuryAccount);
+                require_keys_eq!(treasury_token_account.mint, mint.key(), CustomError::InvalidTreasuryAccount);
+
+                // With the transfer fee extension, the amount is grossed up so that the destination receives it. The whole balance
+                // is withdrawn as is, so the fee is withheld from it a second time, after the one paid by the users
+                let amount = match amount {
+                    Some(amount) => payment_gross_amount(&mint.to_account_info(), amount)?,
+                    None => treasury_token_account.amount,
+                };
+                require!(amount <= treasury_token_account.amount, CustomError::InsufficientTreasuryFunds);
+
+                // Transfer the amount, or the whole balance, to the destination token account
+                let cpi_program = token_program.to_account_info();
+                let cpi_accounts = TransferChecked {
+                    from: treasury_token_account.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: destination_token_account.to_account_info(),
+                    authority: self.treasury.to_account_info(),
+                };
+                let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+                transfer_checked(cpi_context, amount, mint.decimals)
+            },
+            (None, None, None, None) => {
+                let destination = self.destination.as_ref().ok_or(CustomError::InvalidWithdrawAccounts)?;
+
+                // The treasury keeps its rent exemption
+                let now = Clock::get()?.unix_timestamp;
+                let mut reserved = Rent::get()?.minimum_balance(0);
+
+                // With a sale goal, the SOL is kept in escrow until the goal is met. If the goal is not met by the deadline, 
+                // the payments not refunded yet stay in the treasury and the rest (such as bot taxes) can be withdrawn
+                if let Some(sale_goal) = self.config.sale_goal {
+                    require!(sale_goal.is_met() || sale_goal.is_failed(now), CustomError::SaleGoalNotMet);
+                    if sale_goal.is_failed(now) {
+                        reserved += sale_goal.outstanding_refunds();
+                    }
+                }
+
+                // With a rebate auction, the SOL can only be withdrawn once the auction settles, and the rebates not claimed yet 
+                // (including the pending rebates of a replaced auction) stay in the treasury
+                if let Some(dutch_auction) = self.config.dutch_auction.filter(|dutch_auction| dutch_auction.rebate) {
+                    let sold_out = self.config.current_supply >= self.config.total_supply;
+                    require!(dutch_auction.is_settled(now, sold_out), CustomError::AuctionNotSettled);
+                    reserved += dutch_auction.outstanding_rebates(sold_out);
+                }
+                if let Some(pending_rebates) = self.config.pending_rebates {
+                    reserved += pending_rebates.outstanding;
+                }
+                let available = self.treasury.lamports().saturating_sub(reserved);
+                let amount = amount.unwrap_or(available);
+                require!(amount <= available, CustomError::InsufficientTreasuryFunds);
+
+                // Transfer the amount, or the whole available balance, to the destination
+                let cpi_program = self.system_program.to_account_info();
+                let cpi_accounts = Transfer {
+                    from: self.treasury.to_account_info(),
+                    to: destination.to_account_info(),
+                };
+                let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+                transfer(cpi_context, amount)
+            },
+            _ => Err(CustomError::InvalidWithdrawAccounts.into()),
+        }
+    }
+}
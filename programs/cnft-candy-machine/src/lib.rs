@@ -6,6 +6,7 @@ mod state;
 mod instructions;
 mod constants;
 mod errors;
+mod utils;
 
 use state::*;
 use instructions::*;
@@ -15,8 +16,10 @@ use errors::*;
 pub mod cnft_candy_machine {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>, total_supply: u32, price_sol: Option<u64>, price_spl: Option<u64>, spl_address: Option<Pubkey>, max_depth: u32, max_buffer_size: u32) -> Result<()> {
-        ctx.accounts.init_config(total_supply, price_sol, price_spl, spl_address, &ctx.bumps)?;
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize(ctx: Context<Initialize>, total_supply: u32, price_sol: Option<u64>, price_spl: Option<u64>, allow_mint_amount: Option<u64>, hidden_settings: Option<HiddenSettings>, max_depth: u32, max_buffer_size: u32) -> Result<()> {
+        ctx.accounts.init_config(total_supply, price_sol, price_spl, allow_mint_amount, hidden_settings, &ctx.bumps)?;
+        ctx.accounts.fund_treasury()?;
         ctx.accounts.init_tree(max_depth, max_buffer_size)
     }
 
@@ -24,15 +27,123 @@ pub mod cnft_candy_machine {
         ctx.accounts.set_tree_status(status)
     }
 
+    pub fn set_sale_window(ctx: Context<SetSaleWindow>, start_time: Option<i64>, end_time: Option<i64>) -> Result<()> {
+        ctx.accounts.set_sale_window(start_time, end_time)
+    }
+
+    pub fn set_phases(ctx: Context<SetPhases>, phases: Vec<PhaseParams>) -> Result<()> {
+        ctx.accounts.set_phases(phases)
+    }
+
+    pub fn set_payment_mints<'info>(ctx: Context<'_, '_, '_, 'info, SetPaymentMints<'info>>, payment_mints: Vec<PaymentMint>) -> Result<()> {
+        ctx.accounts.set_payment_mints(payment_mints, ctx.remaining_accounts)
+    }
+
+    pub fn set_recipients(ctx: Context<SetRecipients>, recipients: Vec<Recipient>) -> Result<()> {
+        ctx.accounts.set_recipients(recipients)
+    }
+
+    pub fn set_max_per_wallet(ctx: Context<SetMaxPerWallet>, max_per_wallet: Option<u32>) -> Result<()> {
+        ctx.accounts.set_max_per_wallet(max_per_wallet)
+    }
+
+    pub fn set_bot_tax(ctx: Context<SetBotTax>, bot_tax: Option<u64>) -> Result<()> {
+        ctx.accounts.set_bot_tax(bot_tax)
+    }
+
+    pub fn set_gatekeeper(ctx: Context<SetGatekeeper>, gatekeeper: Option<Gatekeeper>) -> Result<()> {
+        ctx.accounts.set_gatekeeper(gatekeeper)
+    }
+
+    pub fn set_token_gate(ctx: Context<SetTokenGate>, token_gate: Option<TokenGate>) -> Result<()> {
+        ctx.accounts.set_token_gate(token_gate)
+    }
+
+    pub fn set_nft_gate(ctx: Context<SetNftGate>, nft_gate: Option<NftGate>) -> Result<()> {
+        ctx.accounts.set_nft_gate(nft_gate)
+    }
+
+    pub fn set_cnft_gate(ctx: Context<SetCnftGate>, cnft_gate: Option<CnftGate>) -> Result<()> {
+        ctx.accounts.set_cnft_gate(cnft_gate)
+    }
+
+    pub fn set_dutch_auction(ctx: Context<SetDutchAuction>, dutch_auction: Option<DutchAuctionParams>) -> Result<()> {
+        ctx.accounts.set_dutch_auction(dutch_auction)
+    }
+
+    pub fn set_bonding_curve(ctx: Context<SetBondingCurve>, bonding_curve: Option<BondingCurve>) -> Result<()> {
+        ctx.accounts.set_bonding_curve(bonding_curve)
+    }
+
+    pub fn set_gate_discounts(ctx: Context<SetGateDiscounts>, gate_discounts: Vec<GateDiscount>) -> Result<()> {
+        ctx.accounts.set_gate_discounts(gate_discounts)
+    }
+
+    pub fn set_usd_price(ctx: Context<SetUsdPrice>, usd_price: Option<UsdPrice>) -> Result<()> {
+        ctx.accounts.set_usd_price(usd_price)
+    }
+
+    pub fn set_sale_goal(ctx: Context<SetSaleGoal>, sale_goal: Option<SaleGoalParams>) -> Result<()> {
+        ctx.accounts.set_sale_goal(sale_goal)
+    }
+
     pub fn create_collection(ctx: Context<CreateCollection>, name: String, symbol: String, uri: String) -> Result<()> {
         ctx.accounts.create_collection(name, symbol, uri)
     }
 
-    pub fn add_allow_list(ctx: Context<AllowList>, user: Pubkey, amount: u8) -> Result<()> {
-        ctx.accounts.add(user, amount)
+    pub fn add_allow_list(ctx: Context<AllowList>, user: Pubkey, amount: u8, discount_bps: Option<u16>) -> Result<()> {
+        ctx.accounts.add(user, amount, discount_bps, &ctx.bumps)
+    }
+
+    pub fn add_allow_list_batch<'info>(ctx: Context<'_, '_, '_, 'info, AllowListBatch<'info>>, users: Vec<AllowListItem>) -> Result<()> {
+        ctx.accounts.add_batch(users, ctx.remaining_accounts)
+    }
+
+    pub fn update_allow_list(ctx: Context<UpdateAllowList>, user: Pubkey, amount: u8, discount_bps: Option<u16>) -> Result<()> {
+        ctx.accounts.update(user, amount, discount_bps)
+    }
+
+    pub fn remove_allow_list(ctx: Context<RemoveAllowList>, user: Pubkey) -> Result<()> {
+        ctx.accounts.remove(user)
+    }
+
+    pub fn set_allow_list_root(ctx: Context<SetAllowListRoot>, root: Option<[u8; 32]>) -> Result<()> {
+        ctx.accounts.set_allow_list_root(root)
+    }
+
+    pub fn initialize_config_lines(ctx: Context<InitializeConfigLines>, random: bool) -> Result<()> {
+        ctx.accounts.initialize_config_lines(random, &ctx.bumps)
+    }
+
+    pub fn add_config_lines(ctx: Context<AddConfigLines>, index: u32, lines: Vec<ConfigLine>) -> Result<()> {
+        ctx.accounts.add(index, lines)
+    }
+
+    pub fn reveal<'info>(ctx: Context<'_, '_, '_, 'info, Reveal<'info>>, root: [u8; 32], nonce: u64, index: u32) -> Result<()> {
+        ctx.accounts.reveal(root, nonce, index, ctx.remaining_accounts)
+    }
+
+    pub fn mint<'info>(ctx: Context<'_, '_, '_, 'info, MintNFT<'info>>, currency: Option<Pubkey>, max_price: Option<u64>, allow_list_proof: Option<AllowListProof>, cnft_gate_proof: Option<CnftGateProof>) -> Result<()> {
+        ctx.accounts.mint_cnft(currency, max_price, allow_list_proof, cnft_gate_proof, &ctx.bumps, ctx.remaining_accounts)
+    }
+
+    pub fn withdraw(ctx: Context<Withdraw>, amount: Option<u64>) -> Result<()> {
+        ctx.accounts.withdraw(amount, &ctx.bumps)
+    }
+
+    pub fn claim_rebate(ctx: Context<ClaimRebate>) -> Result<()> {
+        ctx.accounts.claim_rebate(&ctx.bumps)
+    }
+
+    pub fn refund<'info>(ctx: Context<'_, '_, '_, 'info, Refund<'info>>, leaves: Vec<RefundLeaf>) -> Result<()> {
+        ctx.accounts.refund(leaves, &ctx.bumps, ctx.remaining_accounts)
+    }
+
+    pub fn release_token_gate(ctx: Context<ReleaseTokenGate>) -> Result<()> {
+        ctx.accounts.release_token_gate()
     }
 
-    pub fn mint<'info>(ctx: Context<'_, '_, '_, 'info, MintNFT<'info>>, name: String, symbol: String, uri: String, pay_sol: bool) -> Result<()> {
-        ctx.accounts.mint_cnft(name, symbol, uri, pay_sol, ctx.remaining_accounts)
+    pub fn close(ctx: Context<Close>) -> Result<()> {
+        ctx.accounts.close(&ctx.bumps)
     }
 }
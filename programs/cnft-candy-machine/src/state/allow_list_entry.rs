@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{ANCHOR_DESCRIMINATOR_SIZE, PUBKEY_SIZE};
+
+#[account]
+pub struct AllowListEntry {
+    pub user: Pubkey,
+    pub allotted: u8,
+    pub claimed: u8,
+    pub discount_bps: Option<u16>,
+    pub merkle: bool,
+    pub bump: u8,
+}
+
+impl Space for AllowListEntry {
+    const INIT_SPACE: usize = ANCHOR_DESCRIMINATOR_SIZE + PUBKEY_SIZE + 1 + 1 + (1 + 2) + 1 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AllowListProof {
+    pub amount: u8,
+    pub proof: Vec<[u8; 32]>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AllowListItem {
+    pub user: Pubkey,
+    pub amount: u8,
+    pub discount_bps: Option<u16>,
+}
@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::ANCHOR_DESCRIMINATOR_SIZE;
+
+#[account]
+pub struct AuctionReceipt {
+    pub paid: u64,
+    pub base_paid: u64,
+    pub bump: u8,
+}
+
+impl Space for AuctionReceipt {
+    const INIT_SPACE: usize = ANCHOR_DESCRIMINATOR_SIZE + 8 + 8 + 1;
+}
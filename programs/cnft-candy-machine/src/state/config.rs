@@ -1,24 +1,145 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak::hashv;
+use mpl_bubblegum::types::{
+    Collection, 
+    MetadataArgs, 
+    TokenProgramVersion, 
+    TokenStandard
+};
 
-use crate::constants::{ANCHOR_DESCRIMINATOR_SIZE, PUBKEY_SIZE, TREE_STATUS_SIZE, U32_SIZE, VEC_PREFIX_SIZE};
+use crate::utils::apply_bps_ceil;
+use crate::constants::{ANCHOR_DESCRIMINATOR_SIZE, BASIS_POINTS, MAX_CURVE_POINTS, MAX_GATE_DISCOUNTS, MAX_NAME_LENGTH, MAX_PAYMENT_MINTS, MAX_RECIPIENTS, MAX_URI_LENGTH, PUBKEY_SIZE, TREE_STATUS_SIZE, U32_SIZE, VEC_PREFIX_SIZE};
 
 #[account]
 pub struct Config {
     pub authority: Pubkey,
-    pub allow_list: Vec<AllowListStruct>,
+    pub allow_list_root: Option<[u8; 32]>,
     pub allow_mint: Option<Pubkey>,
+    pub allow_mint_amount: u64,
     pub collection: Pubkey,
     pub total_supply: u32,
     pub current_supply: u32,
     pub price_sol: Option<u64>,
-    pub price_spl: Option<u64>,
-    pub spl_address: Option<Pubkey>,
+    pub payment_mints: Vec<PaymentMint>,
+    pub recipients: Vec<Recipient>,
     pub status: TreeStatus,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub phases: Vec<Phase>,
+    pub max_per_wallet: Option<u32>,
+    pub bot_tax: Option<u64>,
+    pub gatekeeper: Option<Gatekeeper>,
+    pub token_gate: Option<TokenGate>,
+    pub nft_gate: Option<NftGate>,
+    pub cnft_gate: Option<CnftGate>,
+    pub dutch_auction: Option<DutchAuction>,
+    pub bonding_curve: Option<BondingCurve>,
+    pub gate_discounts: Vec<GateDiscount>,
+    pub usd_price: Option<UsdPrice>,
+    pub sale_goal: Option<SaleGoal>,
+    pub hidden_settings: Option<HiddenSettings>,
+    pub pending_rebates: Option<PendingRebates>,
     pub bump: u8,
 }
 
 impl Space for Config {
-    const INIT_SPACE: usize = ANCHOR_DESCRIMINATOR_SIZE + PUBKEY_SIZE + VEC_PREFIX_SIZE + (1 + PUBKEY_SIZE) + PUBKEY_SIZE + (U32_SIZE * 2) + (1 + 8) + (1 + 8) + (1 + PUBKEY_SIZE) + TREE_STATUS_SIZE + 1; 
+    const INIT_SPACE: usize = ANCHOR_DESCRIMINATOR_SIZE + PUBKEY_SIZE + (1 + 32) + (1 + PUBKEY_SIZE) + 8 + PUBKEY_SIZE + (U32_SIZE * 2) + (1 + 8) + VEC_PREFIX_SIZE + (MAX_PAYMENT_MINTS * PaymentMint::INIT_SPACE) + VEC_PREFIX_SIZE + (MAX_RECIPIENTS * Recipient::INIT_SPACE) + TREE_STATUS_SIZE + (1 + 8) + (1 + 8) + VEC_PREFIX_SIZE + (1 + U32_SIZE) + (1 + 8) + (1 + Gatekeeper::INIT_SPACE) + (1 + TokenGate::INIT_SPACE) + (1 + NftGate::INIT_SPACE) + (1 + CnftGate::INIT_SPACE) + (1 + DutchAuction::INIT_SPACE) + (1 + BondingCurve::INIT_SPACE) + VEC_PREFIX_SIZE + (MAX_GATE_DISCOUNTS * GateDiscount::INIT_SPACE) + (1 + UsdPrice::INIT_SPACE) + (1 + SaleGoal::INIT_SPACE) + (1 + HiddenSettings::INIT_SPACE) + (1 + PendingRebates::INIT_SPACE) + 1; 
+}
+
+impl Config {
+    pub fn active_phase(&self, now: i64) -> Option<usize> {
+        // Phases are ordered and do not overlap, so the active phase is the one whose window contains the current time
+        self.phases.iter().position(|phase| now >= phase.start_time && now < phase.end_time)
+    }
+
+    pub fn phase_payment_mints(&self, phase: &Phase) -> Vec<PaymentMint> {
+        // A phase can override the price of the accepted payment mints, the other ones keep the config price
+        self.payment_mints
+            .iter()
+            .map(|payment_mint| PaymentMint {
+                mint: payment_mint.mint,
+                price: phase.payment_mints
+                    .iter()
+                    .find(|phase_payment_mint| phase_payment_mint.mint == payment_mint.mint)
+                    .map_or(payment_mint.price, |phase_payment_mint| phase_payment_mint.price),
+            })
+            .collect()
+    }
+
+    pub fn gate_discount(&self, gate: Gate) -> Option<u16> {
+        self.gate_discounts
+            .iter()
+            .find(|gate_discount| gate_discount.gate == gate)
+            .map(|gate_discount| gate_discount.discount_bps)
+    }
+
+    pub fn has_rebates(&self) -> bool {
+        // Rebates are paid from the treasury for a rebate auction, and for the pending rebates of a replaced one
+        self.dutch_auction.is_some_and(|dutch_auction| dutch_auction.rebate) || self.pending_rebates.is_some()
+    }
+
+    pub fn treasury_payouts_compatible(recipients: bool, rebate: bool, sale_goal: bool) -> bool {
+        // Rebates of a Dutch auction and refunds of a sale goal are paid back from the treasury, so the SOL payments must stay in it:
+        // they cannot be shared with recipients, and the treasury cannot back both rebates and refunds. At most one of them can be set
+        [recipients, rebate, sale_goal].into_iter().filter(|set| *set).count() <= 1
+    }
+
+    pub fn split_payment(&self, amount: u64) -> Vec<(Pubkey, u64)> {
+        // Each recipient gets its share of the payment in basis points. The rounding remainder goes to the last recipient,
+        // so the shares always add up to the payment
+        let mut remaining = amount;
+        self.recipients
+            .iter()
+            .enumerate()
+            .map(|(index, recipient)| {
+                let share = match index == self.recipients.len() - 1 {
+                    true => remaining,
+                    false => (amount as u128 * recipient.bps as u128 / BASIS_POINTS as u128) as u64,
+                };
+                remaining -= share;
+                (recipient.address, share)
+            })
+            .collect()
+    }
+
+    pub fn verify_allow_list_proof(&self, user: &Pubkey, amount: u8, proof: &[[u8; 32]]) -> bool {
+        let Some(root) = self.allow_list_root else {
+            return false;
+        };
+
+        // The leaf is the hash of the user key and the allotted amount. Each pair of nodes is hashed in sorted order,
+        // so the proof does not need to specify on which side each node is
+        let mut node = hashv(&[user.as_ref(), &[amount]]).to_bytes();
+        for sibling in proof {
+            node = match node <= *sibling {
+                true => hashv(&[&node, sibling]).to_bytes(),
+                false => hashv(&[sibling, &node]).to_bytes(),
+            };
+        }
+
+        node == root
+    }
+
+    pub fn metadata_args(&self, name: String, symbol: String, uri: String) -> MetadataArgs {
+        // Metadata of the minted cNFTs. Hidden cNFTs are kept mutable so that they can be revealed later
+        MetadataArgs {
+            name,
+            symbol,
+            uri,
+            creators: vec![],
+            seller_fee_basis_points: 0,
+            primary_sale_happened: false,
+            is_mutable: self.hidden_settings.is_some(),
+            edition_nonce: Some(0),
+            uses: None,
+            collection: Some(Collection {
+                verified: true,
+                key: self.collection,
+            }),
+            token_program_version: TokenProgramVersion::Original,
+            token_standard: Some(TokenStandard::NonFungible),
+        }
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
@@ -28,8 +149,289 @@ pub enum TreeStatus {
     Public,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub enum Gate {
+    Public,
+    AllowList,
+    AllowMint,
+    TokenHolder,
+    NftHolder,
+    CnftHolder,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct PaymentMint {
+    pub mint: Pubkey,
+    pub price: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct GateDiscount {
+    pub gate: Gate,
+    pub discount_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct UsdPrice {
+    pub price_cents: u64,
+    pub oracle: Pubkey,
+    pub max_staleness: i64,
+    pub max_confidence_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct Recipient {
+    pub address: Pubkey,
+    pub bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Phase {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub price_sol: Option<u64>,
+    #[max_len(MAX_PAYMENT_MINTS)]
+    pub payment_mints: Vec<PaymentMint>,
+    pub gate: Gate,
+    pub supply_cap: Option<u32>,
+    pub minted: u32,
+}
+
+// Settings of a phase passed to `set_phases`. The minted amount of each phase is tracked by the program
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PhaseParams {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub price_sol: Option<u64>,
+    pub payment_mints: Vec<PaymentMint>,
+    pub gate: Gate,
+    pub supply_cap: Option<u32>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub enum GatekeeperMode {
+    CoSigner,
+    Ed25519,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct Gatekeeper {
+    pub key: Pubkey,
+    pub mode: GatekeeperMode,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct TokenGate {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub single_use: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct NftGate {
+    pub collection: Pubkey,
+    pub single_use: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct CnftGate {
+    pub merkle_tree: Pubkey,
+    pub single_use: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct DutchAuction {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub floor_bps: u16,
+    pub step_interval: u32,
+    pub rebate: bool,
+    pub clearing_bps: u16,
+    pub total_paid: u64,
+    pub total_base_paid: u64,
+    pub rebated: u64,
+    pub receipts: u32,
+}
+
+// Settings of a Dutch auction passed to `set_dutch_auction`. The sales and rebates of the auction are tracked by the program
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct DutchAuctionParams {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub floor_bps: u16,
+    pub step_interval: u32,
+    pub rebate: bool,
+}
+
+impl DutchAuction {
+    pub fn price_bps(&self, now: i64) -> u64 {
+        // The price decays linearly from the full price at the start to the floor at the end. With a step interval,
+        // the price only drops once per interval
+        let duration = self.end_time - self.start_time;
+        let mut elapsed = (now - self.start_time).clamp(0, duration);
+        if self.step_interval > 0 {
+            elapsed -= elapsed % self.step_interval as i64;
+        }
+        let decay = (BASIS_POINTS - self.floor_bps) as i64 * elapsed / duration;
+        (BASIS_POINTS as i64 - decay) as u64
+    }
+
+    pub fn is_settled(&self, now: i64, sold_out: bool) -> bool {
+        sold_out || now >= self.end_time
+    }
+
+    pub fn settled_bps(&self, sold_out: bool) -> u64 {
+        // If the collection sold out, the clearing price is the price of the last mint, otherwise every later mint is at the floor
+        match sold_out {
+            true => self.clearing_bps as u64,
+            false => self.floor_bps as u64,
+        }
+    }
+
+    pub fn rebate_amount(&self, paid: u64, base_paid: u64, sold_out: bool) -> u64 {
+        rebate_amount(paid, base_paid, self.settled_bps(sold_out))
+    }
+
+    pub fn outstanding_rebates(&self, sold_out: bool) -> u64 {
+        // Once every receipt is claimed, the rounding left over from the rebate of the totals is no longer owed
+        match self.receipts {
+            0 => 0,
+            _ => self.rebate_amount(self.total_paid, self.total_base_paid, sold_out).saturating_sub(self.rebated),
+        }
+    }
+
+    pub fn pending_rebates(&self, sold_out: bool) -> PendingRebates {
+        PendingRebates {
+            settled_bps: self.settled_bps(sold_out) as u16,
+            outstanding: self.outstanding_rebates(sold_out),
+            receipts: self.receipts,
+        }
+    }
+}
+
+// Rebates of a settled auction that was replaced or removed before every receipt was claimed
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
-pub struct AllowListStruct {
-    pub user: Pubkey,
-    pub amount: u8,
-}
\ No newline at end of file
+pub struct PendingRebates {
+    pub settled_bps: u16,
+    pub outstanding: u64,
+    pub receipts: u32,
+}
+
+impl PendingRebates {
+    pub fn rebate_amount(&self, paid: u64, base_paid: u64) -> u64 {
+        rebate_amount(paid, base_paid, self.settled_bps as u64)
+    }
+}
+
+fn rebate_amount(paid: u64, base_paid: u64, settled_bps: u64) -> u64 {
+    // The clearing price of the mints is rounded up, so the rebates of the receipts never add up to more than the rebate
+    // of the totals, which is what withdraw keeps in the treasury
+    paid.saturating_sub(apply_bps_ceil(base_paid, settled_bps))
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum BondingCurve {
+    Linear {
+        increment_bps: u32,
+    },
+    ExponentialStep {
+        step_size: u32,
+        growth_bps: u32,
+    },
+    Piecewise {
+        #[max_len(MAX_CURVE_POINTS)]
+        points: Vec<CurvePoint>,
+    },
+}
+
+impl BondingCurve {
+    pub fn price_bps(&self, current_supply: u32) -> u64 {
+        match self {
+            // The price rises by the increment for each minted cNFT
+            BondingCurve::Linear { increment_bps } => BASIS_POINTS as u64 + *increment_bps as u64 * current_supply as u64,
+            // The price grows by the growth rate every step of minted cNFTs, compounded by squaring
+            BondingCurve::ExponentialStep { step_size, growth_bps } => {
+                let mut base = BASIS_POINTS as u128 + *growth_bps as u128;
+                let mut steps = current_supply / step_size;
+                let mut bps = BASIS_POINTS as u128;
+                while steps > 0 {
+                    if steps & 1 == 1 {
+                        bps = (bps * base / BASIS_POINTS as u128).min(u64::MAX as u128);
+                    }
+                    base = (base * base / BASIS_POINTS as u128).min(u64::MAX as u128);
+                    steps >>= 1;
+                }
+                bps as u64
+            },
+            // The price is the one of the last point reached by the current supply, or the full price before the first point
+            BondingCurve::Piecewise { points } => points
+                .iter()
+                .rev()
+                .find(|point| current_supply >= point.supply)
+                .map_or(BASIS_POINTS as u64, |point| point.bps as u64),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct CurvePoint {
+    pub supply: u32,
+    pub bps: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct SaleGoal {
+    pub goal: u64,
+    pub deadline: i64,
+    pub raised: u64,
+    pub refunded: u64,
+}
+
+// Settings of a sale goal passed to `set_sale_goal`. The amount raised is tracked by the program
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct SaleGoalParams {
+    pub goal: u64,
+    pub deadline: i64,
+}
+
+impl SaleGoal {
+    pub fn is_met(&self) -> bool {
+        self.raised >= self.goal
+    }
+
+    pub fn is_failed(&self, now: i64) -> bool {
+        !self.is_met() && now >= self.deadline
+    }
+
+    pub fn outstanding_refunds(&self) -> u64 {
+        self.raised - self.refunded
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CnftGateProof {
+    pub root: [u8; 32],
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub nonce: u64,
+    pub index: u32,
+    pub delegate: Option<Pubkey>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RefundLeaf {
+    pub root: [u8; 32],
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub nonce: u64,
+    pub index: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct HiddenSettings {
+    #[max_len(MAX_NAME_LENGTH)]
+    pub name: String,
+    #[max_len(MAX_URI_LENGTH)]
+    pub uri: String,
+    pub hash: [u8; 32],
+}
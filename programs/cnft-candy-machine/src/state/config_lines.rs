@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::{ANCHOR_DESCRIMINATOR_SIZE, CONFIG_LINE_SIZE, MAX_NAME_LENGTH, MAX_URI_LENGTH, U32_SIZE},
+    CustomError,
+};
+
+// The account data is laid out as the header below, followed by `line_count` fixed-size slots.
+// Each slot holds an entry of the random order index table (u32), followed by the line itself
+// in `CONFIG_LINE_SIZE` bytes (zero padded name, then zero padded uri). The slots are accessed
+// directly from the account data so that minting only has to read the line it needs.
+#[account]
+pub struct ConfigLines {
+    pub line_count: u32,
+    pub random: bool,
+    pub bump: u8,
+}
+
+impl Space for ConfigLines {
+    const INIT_SPACE: usize = ANCHOR_DESCRIMINATOR_SIZE + U32_SIZE + 1 + 1;
+}
+
+const SLOT_SIZE: usize = U32_SIZE + CONFIG_LINE_SIZE;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConfigLine {
+    pub name: String,
+    pub uri: String,
+}
+
+impl ConfigLines {
+    pub fn space(line_count: usize) -> usize {
+        Self::INIT_SPACE + line_count * SLOT_SIZE
+    }
+
+    pub fn write_line(info: &AccountInfo, index: u32, line: &ConfigLine) -> Result<()> {
+        // Check if the name and uri fit in their slots
+        require!(line.name.len() <= MAX_NAME_LENGTH, CustomError::ConfigLineTooLong);
+        require!(line.uri.len() <= MAX_URI_LENGTH, CustomError::ConfigLineTooLong);
+
+        let mut data = info.try_borrow_mut_data()?;
+        let offset = Self::space(index as usize) + U32_SIZE;
+        let slot = data.get_mut(offset..offset + CONFIG_LINE_SIZE).ok_or(CustomError::InvalidConfigLineIndex)?;
+
+        // Clear the slot and copy the name and uri into it
+        slot.fill(0);
+        slot[..line.name.len()].copy_from_slice(line.name.as_bytes());
+        slot[MAX_NAME_LENGTH..MAX_NAME_LENGTH + line.uri.len()].copy_from_slice(line.uri.as_bytes());
+
+        Ok(())
+    }
+
+    pub fn read_line(info: &AccountInfo, index: u32) -> Result<ConfigLine> {
+        let data = info.try_borrow_data()?;
+        let offset = Self::space(index as usize) + U32_SIZE;
+        let slot = data.get(offset..offset + CONFIG_LINE_SIZE).ok_or(CustomError::InvalidConfigLineIndex)?;
+
+        Ok(ConfigLine {
+            name: Self::unpad(&slot[..MAX_NAME_LENGTH])?,
+            uri: Self::unpad(&slot[MAX_NAME_LENGTH..])?,
+        })
+    }
+
+    pub fn swap_remove(info: &AccountInfo, position: u32, last: u32) -> Result<u32> {
+        // The index table holds the line indices that have not been minted yet in positions [0, last].
+        // An entry stores the line index + 1, with 0 meaning the entry still holds its own position,
+        // so the table does not need to be initialized
+        let mut data = info.try_borrow_mut_data()?;
+        let picked = Self::table_entry(&data, position)?;
+        let moved = Self::table_entry(&data, last)?;
+
+        // Move the last unminted line index into the picked position, so draws stay O(1)
+        let offset = Self::space(position as usize);
+        data[offset..offset + U32_SIZE].copy_from_slice(&(moved + 1).to_le_bytes());
+
+        Ok(picked)
+    }
+
+    fn table_entry(data: &[u8], position: u32) -> Result<u32> {
+        let offset = Self::space(position as usize);
+        let entry = data.get(offset..offset + U32_SIZE).ok_or(CustomError::InvalidConfigLineIndex)?;
+        match u32::from_le_bytes(entry.try_into().unwrap()) {
+            0 => Ok(position),
+            value => Ok(value - 1),
+        }
+    }
+
+    fn unpad(bytes: &[u8]) -> Result<String> {
+        // Strip the zero padding at the end of the slot
+        let len = bytes.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+        String::from_utf8(bytes[..len].to_vec()).map_err(|_| CustomError::InvalidConfigLine.into())
+    }
+}
@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::ANCHOR_DESCRIMINATOR_SIZE;
+
+#[account]
+pub struct GateReceipt {
+    pub used: bool,
+    pub bump: u8,
+}
+
+impl Space for GateReceipt {
+    const INIT_SPACE: usize = ANCHOR_DESCRIMINATOR_SIZE + 1 + 1;
+}
@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::ANCHOR_DESCRIMINATOR_SIZE;
+
+#[account]
+pub struct GatekeeperNonce {
+    pub last_nonce: u64,
+    pub bump: u8,
+}
+
+impl Space for GatekeeperNonce {
+    const INIT_SPACE: usize = ANCHOR_DESCRIMINATOR_SIZE + 8 + 1;
+}
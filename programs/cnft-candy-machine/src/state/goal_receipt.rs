@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{
+    ANCHOR_DESCRIMINATOR_SIZE, 
+    U32_SIZE
+};
+
+#[account]
+pub struct GoalReceipt {
+    pub paid: u64,
+    pub minted: u32,
+    pub bump: u8,
+}
+
+impl Space for GoalReceipt {
+    const INIT_SPACE: usize = ANCHOR_DESCRIMINATOR_SIZE + 8 + U32_SIZE + 1;
+}
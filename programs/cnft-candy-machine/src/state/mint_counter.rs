@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{ANCHOR_DESCRIMINATOR_SIZE, U32_SIZE};
+
+#[account]
+pub struct MintCounter {
+    pub count: u32,
+    pub bump: u8,
+}
+
+impl Space for MintCounter {
+    const INIT_SPACE: usize = ANCHOR_DESCRIMINATOR_SIZE + U32_SIZE + 1;
+}
@@ -1,3 +1,17 @@
 pub mod config;
+pub mod config_lines;
+pub mod allow_list_entry;
+pub mod mint_counter;
+pub mod gatekeeper_nonce;
+pub mod gate_receipt;
+pub mod auction_receipt;
+pub mod goal_receipt;
 
-pub use config::*;
\ No newline at end of file
+pub use config::*;
+pub use config_lines::*;
+pub use allow_list_entry::*;
+pub use mint_counter::*;
+pub use gatekeeper_nonce::*;
+pub use gate_receipt::*;
+pub use auction_receipt::*;
+pub use goal_receipt::*;
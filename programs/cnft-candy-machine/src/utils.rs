@@ -0,0 +1,129 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
+use anchor_spl::token::ID as TOKEN_PROGRAM_ID;
+use anchor_spl::token_2022::ID as TOKEN_2022_PROGRAM_ID;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{
+        transfer_fee::TransferFeeConfig, 
+        BaseStateWithExtensions, 
+        ExtensionType, 
+        StateWithExtensions
+    }, 
+    state::Mint
+};
+
+use crate::{
+    constants::BASIS_POINTS, 
+    state::UsdPrice, 
+    CustomError
+};
+
+// Token-2022 mint extensions that still allow a plain transfer between two token accounts. Permanent delegate mints are
+// not supported, since the delegate could move the payments out of the treasury or recipient token accounts at any time
+const SUPPORTED_PAYMENT_MINT_EXTENSIONS: [ExtensionType; 9] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MintCloseAuthority,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::GroupPointer,
+    ExtensionType::TokenGroup,
+    ExtensionType::GroupMemberPointer,
+    ExtensionType::TokenGroupMember,
+];
+
+// Layout of a Pyth price account
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_EXPO_OFFSET: usize = 20;
+const PYTH_TIMESTAMP_OFFSET: usize = 96;
+const PYTH_PRICE_OFFSET: usize = 208;
+const PYTH_CONF_OFFSET: usize = 216;
+const PYTH_STATUS_OFFSET: usize = 224;
+const PYTH_PRICE_ACCOUNT_SIZE: usize = 240;
+const PYTH_STATUS_TRADING: u32 = 1;
+
+pub fn check_payment_mint(mint: &AccountInfo) -> Result<()> {
+    // Check if the account is a Token or Token-2022 mint
+    require!(*mint.owner == TOKEN_PROGRAM_ID || *mint.owner == TOKEN_2022_PROGRAM_ID, CustomError::InvalidPaymentMint);
+
+    // Reject mints with extensions that block or alter transfers, such as non-transferable or transfer hook mints.
+    // Token mints have no extensions
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    for extension in state.get_extension_types()? {
+        require!(SUPPORTED_PAYMENT_MINT_EXTENSIONS.contains(&extension), CustomError::UnsupportedPaymentMint);
+    }
+
+    Ok(())
+}
+
+pub fn payment_gross_amount(mint: &AccountInfo, net_amount: u64) -> Result<u64> {
+    // Token mints have no transfer fee
+    if *mint.owner == TOKEN_PROGRAM_ID {
+        return Ok(net_amount);
+    }
+
+    // With the transfer fee extension, the fee is withheld from the transferred amount, so the amount is grossed up
+    // for the recipient to receive the net amount
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    let Ok(transfer_fee_config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(net_amount);
+    };
+
+    let epoch = Clock::get()?.epoch;
+    let gross_amount = transfer_fee_config
+        .get_epoch_fee(epoch)
+        .calculate_pre_fee_amount(net_amount)
+        .ok_or(CustomError::InvalidPaymentAmount)?;
+
+    Ok(gross_amount)
+}
+
+pub fn apply_bps(amount: u64, bps: u64) -> u64 {
+    // Saturate instead of overflowing, the max price of the mint protects the user from unexpected prices
+    u64::try_from(amount as u128 * bps as u128 / BASIS_POINTS as u128).unwrap_or(u64::MAX)
+}
+
+pub fn apply_bps_ceil(amount: u64, bps: u64) -> u64 {
+    // Same as apply_bps, rounding up instead of down
+    u64::try_from((amount as u128 * bps as u128).div_ceil(BASIS_POINTS as u128)).unwrap_or(u64::MAX)
+}
+
+pub fn usd_price_in_lamports(oracle: &AccountInfo, usd_price: &UsdPrice, now: i64) -> Result<u64> {
+    // Read the aggregate SOL/USD price of the Pyth price account
+    let data = oracle.try_borrow_data()?;
+    require!(data.len() >= PYTH_PRICE_ACCOUNT_SIZE, CustomError::InvalidOracle);
+    require!(u32::from_le_bytes(read_bytes(&data, 0)) == PYTH_MAGIC, CustomError::InvalidOracle);
+    let expo = i32::from_le_bytes(read_bytes(&data, PYTH_EXPO_OFFSET));
+    let timestamp = i64::from_le_bytes(read_bytes(&data, PYTH_TIMESTAMP_OFFSET));
+    let price = i64::from_le_bytes(read_bytes(&data, PYTH_PRICE_OFFSET));
+    let conf = u64::from_le_bytes(read_bytes(&data, PYTH_CONF_OFFSET));
+    let status = u32::from_le_bytes(read_bytes(&data, PYTH_STATUS_OFFSET));
+
+    // Check if the price is being published, recent enough and precise enough
+    require!(status == PYTH_STATUS_TRADING && price > 0, CustomError::InvalidOraclePrice);
+    require!(now - timestamp <= usd_price.max_staleness, CustomError::StaleOraclePrice);
+    require!(
+        conf as u128 * BASIS_POINTS as u128 <= price as u128 * usd_price.max_confidence_bps as u128, 
+        CustomError::OracleConfidenceTooWide
+    );
+
+    // One SOL is worth price * 10^expo USD, so the price in lamports is cents * 10^9 / (100 * price * 10^expo)
+    let exponent = 10u128.checked_pow(expo.unsigned_abs());
+    let numerator = usd_price.price_cents as u128 * LAMPORTS_PER_SOL as u128;
+    let denominator = 100 * price as u128;
+    let (Some(numerator), Some(denominator)) = (match expo < 0 {
+        true => (exponent.and_then(|exponent| numerator.checked_mul(exponent)), Some(denominator)),
+        false => (Some(numerator), exponent.and_then(|exponent| denominator.checked_mul(exponent))),
+    }) else {
+        return Err(CustomError::InvalidOraclePrice.into());
+    };
+    let lamports = u64::try_from(numerator / denominator).map_err(|_| CustomError::InvalidOraclePrice)?;
+
+    Ok(lamports)
+}
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+    data[offset..offset + N].try_into().unwrap()
+}
@@ -4,6 +4,8 @@ import { CnftCandyMachine } from "../target/types/cnft_candy_machine";
 import {
   AccountMeta,
   Connection,
+  Ed25519Program,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
   Keypair,
   LAMPORTS_PER_SOL,
   PublicKey,
@@ -13,19 +15,47 @@ import {
 } from "@solana/web3.js"
 import {
   ConcurrentMerkleTreeAccount,
+  MerkleTree,
   SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
   ValidDepthSizePair,
   createAllocTreeIx,
 } from "@solana/spl-account-compression"
-import { SPL_ACCOUNT_COMPRESSION_PROGRAM_ID as BUBBLEGUM_PROGRAM_ID, SPL_NOOP_PROGRAM_ID, findTreeConfigPda } from "@metaplex-foundation/mpl-bubblegum"
+import {
+  SPL_ACCOUNT_COMPRESSION_PROGRAM_ID as BUBBLEGUM_PROGRAM_ID,
+  SPL_NOOP_PROGRAM_ID,
+  MetadataArgsArgs,
+  TokenProgramVersion,
+  TokenStandard,
+  findLeafAssetIdPda,
+  findTreeConfigPda,
+  hashLeaf,
+  hashMetadataCreators,
+  hashMetadataData,
+} from "@metaplex-foundation/mpl-bubblegum"
+import { publicKey } from "@metaplex-foundation/umi";
 import {
   Metaplex,
   keypairIdentity,
   CreateNftOutput,
 } from "@metaplex-foundation/js"
-import { createMint, mintTo, getOrCreateAssociatedTokenAccount, getAssociatedTokenAddressSync } from "@solana/spl-token";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  ExtensionType,
+  TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  createInitializeMintInstruction,
+  createInitializeNonTransferableMintInstruction,
+  createInitializePermanentDelegateInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createMint,
+  getAssociatedTokenAddressSync,
+  getMintLen,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
 import { createUmi } from "@metaplex-foundation/umi-bundle-defaults";
-import { use } from "chai";
+import { assert, use } from "chai";
+import { keccak_256 } from "@noble/hashes/sha3";
 
 describe("cnft-candy-machine", () => {
   // Configure the client to use the local cluster.
@@ -42,15 +72,25 @@ describe("cnft-candy-machine", () => {
 
   const mintAuthority = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("authority"), wallet.publicKey.toBuffer()], program.programId);
 
+  const configLines = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("config_lines"), config[0].toBuffer()], program.programId)[0];
+
+  const treasury = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("treasury"), config[0].toBuffer()], program.programId)[0];
+
+  // Mock SOL/USD price account with the Pyth layout, loaded by the test validator from tests/fixtures
+  const priceOracle = new anchor.web3.PublicKey("FTxLb6zqXiStD7B9ANjrACs8VjCvaKxs2JRS1pzDhPMW");
+
   const mintCollection = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("collection"), config[0].toBuffer()], program.programId);
 
   let allowMint: anchor.web3.PublicKey;
+  let hiddenMachine: CandyMachine;
   let paymentMint: anchor.web3.PublicKey;
 
   const allowedOne = Keypair.generate();
   const allowedTwo = Keypair.generate();
   const allowedThree = Keypair.generate();
   const publicOne = Keypair.generate();
+  const allowedMerkle = Keypair.generate();
+  const gatekeeper = Keypair.generate();
 
   const maxDepthSizePair: ValidDepthSizePair = {
     maxDepth: 14,
@@ -71,6 +111,17 @@ describe("cnft-candy-machine", () => {
   const treeConfigPublicKey = new anchor.web3.PublicKey(treeConfig);
   console.log('treeConfigPublicKey', treeConfigPublicKey.toBase58());
 
+  // The candy machine of the main flow, owned by the provider wallet, so its tests can use the same helpers as the feature tests
+  const mainMachine: CandyMachine = {
+    authority: wallet.payer,
+    config: config[0],
+    configLines,
+    treasury,
+    collection: mintCollection[0],
+    merkleTree: emptyMerkleTree.publicKey,
+    treeConfig: treeConfigPublicKey,
+  };
+
   const confirm = async (signature: string): Promise<string> => {
     const block = await provider.connection.getLatestBlockhash();
     await provider.connection.confirmTransaction({
@@ -80,6 +131,18 @@ describe("cnft-candy-machine", () => {
     return signature;
   };
 
+  const getAllowListEntry = (user: anchor.web3.PublicKey): anchor.web3.PublicKey => {
+    return anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("allow"), config[0].toBuffer(), user.toBuffer()], program.programId)[0];
+  };
+
+  const getMintCounter = (user: anchor.web3.PublicKey): anchor.web3.PublicKey => {
+    return anchor.web3.PublicKey.findProgramAddressSync([config[0].toBuffer(), user.toBuffer()], program.programId)[0];
+  };
+
+  const getAuctionReceipt = (user: anchor.web3.PublicKey): anchor.web3.PublicKey => {
+    return anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("auction"), config[0].toBuffer(), user.toBuffer()], program.programId)[0];
+  };
+
   const getMetadata = async (mint: anchor.web3.PublicKey): Promise<anchor.web3.PublicKey> => {
     return (
       anchor.web3.PublicKey.findProgramAddressSync(
@@ -107,9 +170,232 @@ describe("cnft-candy-machine", () => {
     )[0];
   };
 
+  // Small trees for the candy machines created by the feature tests, without canopy so proofs hold every node
+  const smallDepthSizePair: ValidDepthSizePair = {
+    maxDepth: 3,
+    maxBufferSize: 8,
+  }
+
+  type CandyMachine = {
+    authority: Keypair;
+    config: PublicKey;
+    configLines: PublicKey;
+    treasury: PublicKey;
+    collection: PublicKey;
+    merkleTree: PublicKey;
+    treeConfig: PublicKey;
+  };
+
+  // Create a new candy machine, owned by a new authority, with its own tree and collection. The config is keyed by the authority,
+  // so each feature test can start from a fresh config
+  const createCandyMachine = async (options: {
+    totalSupply: number;
+    priceSol?: anchor.BN | null;
+    priceSpl?: anchor.BN | null;
+    allowMintAmount?: anchor.BN | null;
+    hiddenSettings?: { name: string; uri: string; hash: number[] } | null;
+    allowMint?: PublicKey | null;
+    splMint?: PublicKey | null;
+  }): Promise<CandyMachine> => {
+    const authority = Keypair.generate();
+    await provider.connection.requestAirdrop(authority.publicKey, 10 * LAMPORTS_PER_SOL).then(confirm);
+
+    const merkleTree = Keypair.generate();
+    const allocTreeIx = await createAllocTreeIx(provider.connection, merkleTree.publicKey, authority.publicKey, smallDepthSizePair, 0);
+    await sendAndConfirmTransaction(provider.connection, new Transaction().add(allocTreeIx), [authority, merkleTree]);
+    const treeConfig = new PublicKey(findTreeConfigPda(umi, { merkleTree: publicKey(merkleTree.publicKey.toBase58()) })[0]);
+
+    await program.methods.initialize(
+      options.totalSupply,
+      options.priceSol ?? null,
+      options.priceSpl ?? null,
+      options.allowMintAmount ?? null,
+      options.hiddenSettings ?? null,
+      smallDepthSizePair.maxDepth,
+      smallDepthSizePair.maxBufferSize,
+    )
+    .accounts({
+      authority: authority.publicKey,
+      allowMint: options.allowMint ?? null,
+      splMint: options.splMint ?? null,
+      merkleTree: merkleTree.publicKey,
+      treeConfig,
+    })
+    .signers([authority])
+    .rpc();
+
+    await program.methods.createCollection("Test", "TST", "https://arweave.net/123")
+    .accounts({
+      authority: authority.publicKey,
+    })
+    .signers([authority])
+    .rpc();
+
+    const config = PublicKey.findProgramAddressSync([Buffer.from("config"), authority.publicKey.toBuffer()], program.programId)[0];
+
+    return {
+      authority,
+      config,
+      configLines: PublicKey.findProgramAddressSync([Buffer.from("config_lines"), config.toBuffer()], program.programId)[0],
+      treasury: PublicKey.findProgramAddressSync([Buffer.from("treasury"), config.toBuffer()], program.programId)[0],
+      collection: PublicKey.findProgramAddressSync([Buffer.from("collection"), config.toBuffer()], program.programId)[0],
+      merkleTree: merkleTree.publicKey,
+      treeConfig,
+    };
+  };
+
+  // Initialize the config lines of a candy machine and upload one line per cNFT
+  const loadConfigLines = async (machine: CandyMachine, random: boolean, count: number) => {
+    await program.methods.initializeConfigLines(random)
+    .accounts({
+      authority: machine.authority.publicKey,
+    })
+    .signers([machine.authority])
+    .rpc();
+
+    const lines = Array.from({ length: count }, (_, i) => ({
+      name: `Test #${i + 1}`,
+      uri: `https://arweave.net/${i + 1}`,
+    }));
+
+    await program.methods.addConfigLines(0, lines)
+    .accounts({
+      authority: machine.authority.publicKey,
+    })
+    .signers([machine.authority])
+    .rpc();
+  };
+
+  const setPublic = async (machine: CandyMachine) => {
+    await program.methods.setTreeStatus({ public: {} })
+    .accounts({
+      authority: machine.authority.publicKey,
+    })
+    .signers([machine.authority])
+    .rpc();
+  };
+
+  // Optional accounts of the mint instruction, each test passes the ones it needs
+  const optionalMintAccounts = {
+    configLines: null,
+    allowListEntry: null,
+    mintCounter: null,
+    gatekeeper: null,
+    gatekeeperNonce: null,
+    instructionsSysvar: null,
+    tokenGateAccount: null,
+    tokenGateReceipt: null,
+    tokenGateMint: null,
+    tokenGateEscrow: null,
+    tokenGateTokenProgram: null,
+    nftGateMint: null,
+    nftGateAccount: null,
+    nftGateMetadata: null,
+    nftGateReceipt: null,
+    cnftGateMerkleTree: null,
+    cnftGateReceipt: null,
+    allowMint: null,
+    allowMintAta: null,
+    allowMintTokenProgram: null,
+    paymentMint: null,
+    paymentSource: null,
+    paymentDestination: null,
+    paymentTokenProgram: null,
+    associatedTokenProgram: null,
+    auctionReceipt: null,
+    goalReceipt: null,
+    priceOracle: null,
+  };
+
+  const mintFrom = (machine: CandyMachine, user: Keypair, accounts: object = {}, currency: PublicKey | null = null, cnftGateProof = null, remainingAccounts: AccountMeta[] = [], maxPrice: anchor.BN | null = null) => {
+    return program.methods.mint(currency, maxPrice, null, cnftGateProof)
+    .accounts({
+      ...optionalMintAccounts,
+      user: user.publicKey,
+      authority: machine.authority.publicKey,
+      treeConfig: machine.treeConfig,
+      merkleTree: machine.merkleTree,
+      ...accounts,
+    } as any)
+    .remainingAccounts(remainingAccounts)
+    .signers([user])
+    .rpc();
+  };
+
+  // Metadata of a cNFT minted by a candy machine, as hashed in its leaf
+  const cnftMetadata = (machine: CandyMachine, name: string, uri: string, isMutable: boolean): MetadataArgsArgs => ({
+    name,
+    symbol: "TST",
+    uri,
+    sellerFeeBasisPoints: 0,
+    primarySaleHappened: false,
+    isMutable,
+    editionNonce: 0,
+    tokenStandard: TokenStandard.NonFungible,
+    collection: { verified: true, key: publicKey(machine.collection.toBase58()) },
+    uses: null,
+    tokenProgramVersion: TokenProgramVersion.Original,
+    creators: [],
+  });
+
+  const cnftLeaf = (machine: CandyMachine, owner: PublicKey, index: number, metadata: MetadataArgsArgs): Buffer => {
+    return Buffer.from(hashLeaf(umi, {
+      merkleTree: publicKey(machine.merkleTree.toBase58()),
+      owner: publicKey(owner.toBase58()),
+      leafIndex: index,
+      metadata,
+    }));
+  };
+
+  // Proof of a leaf, rebuilt from every leaf of the tree, and the current root of the tree
+  const leafProof = async (machine: CandyMachine, leaves: Buffer[], index: number) => {
+    const tree = MerkleTree.sparseMerkleTreeFromLeaves(leaves, smallDepthSizePair.maxDepth);
+    const proof = tree.getProof(index).proof.map((node) => ({ pubkey: new PublicKey(node), isSigner: false, isWritable: false }));
+    const root = (await ConcurrentMerkleTreeAccount.fromAccountAddress(provider.connection, machine.merkleTree)).getCurrentRoot();
+    return { proof, root: Array.from(root) };
+  };
+
+  // Refund the cNFT of a sale goal at the given index of the leaves, burning it
+  const refund = async (machine: CandyMachine, user: Keypair, metadata: MetadataArgsArgs, leaves: Buffer[], index: number) => {
+    const { proof, root } = await leafProof(machine, leaves, index);
+
+    return program.methods.refund([{
+      root,
+      dataHash: Array.from(hashMetadataData(metadata)),
+      creatorHash: Array.from(hashMetadataCreators(metadata.creators)),
+      nonce: new anchor.BN(index),
+      index,
+    }])
+    .accounts({
+      user: user.publicKey,
+      authority: machine.authority.publicKey,
+      treeConfig: machine.treeConfig,
+      merkleTree: machine.merkleTree,
+    })
+    .remainingAccounts(proof)
+    .signers([user])
+    .rpc();
+  };
+
+  // Create a Token-2022 mint with a single extension, initialized by the given instruction
+  const createMint2022 = async (extension: ExtensionType, initializeExtension: (mint: PublicKey) => anchor.web3.TransactionInstruction) => {
+    const mint = Keypair.generate();
+    const space = getMintLen([extension]);
+    const lamports = await provider.connection.getMinimumBalanceForRentExemption(space);
+
+    await sendAndConfirmTransaction(provider.connection, new Transaction().add(
+      anchor.web3.SystemProgram.createAccount({ fromPubkey: wallet.publicKey, newAccountPubkey: mint.publicKey, space, lamports, programId: TOKEN_2022_PROGRAM_ID }),
+      initializeExtension(mint.publicKey),
+      createInitializeMintInstruction(mint.publicKey, 6, wallet.publicKey, null, TOKEN_2022_PROGRAM_ID),
+    ), [wallet.payer, mint]);
+
+    return mint.publicKey;
+  };
+
   it("Airdrop SOl to wallet", async () => {
     const tx = await provider.connection.requestAirdrop(allowedOne.publicKey, 10 * LAMPORTS_PER_SOL).then(confirm);
     const tx2 = await provider.connection.requestAirdrop(publicOne.publicKey, 10 * LAMPORTS_PER_SOL).then(confirm);
+    await provider.connection.requestAirdrop(allowedMerkle.publicKey, 10 * LAMPORTS_PER_SOL).then(confirm);
     console.log("\nAirdrop to Allowed User done: ", tx);
     console.log("Airdrop to Public User done: ", tx2);
   });
@@ -154,10 +440,11 @@ describe("cnft-candy-machine", () => {
 
     console.log("\nAllocated tree", signature);
 
-    const tx = await program.methods.initialize(100, new anchor.BN(0.2 * LAMPORTS_PER_SOL), new anchor.BN(5_000_000), paymentMint, 14, 64)
+    const tx = await program.methods.initialize(100, new anchor.BN(0.2 * LAMPORTS_PER_SOL), new anchor.BN(5_000_000), null, null, 14, 64)
     .accounts({
       authority: provider.wallet.publicKey,
       allowMint,
+      splMint: paymentMint,
       merkleTree: emptyMerkleTree.publicKey,
       treeConfig: treeConfigPublicKey,
     })
@@ -178,8 +465,37 @@ describe("cnft-candy-machine", () => {
     console.log("Your transaction signature", tx);
   })
 
+  it("Initialize config lines", async () => {
+    const tx = await program.methods.initializeConfigLines(false)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    console.log("\nConfig lines initialized");
+    console.log("Your transaction signature", tx);
+  });
+
+  it("Add config lines", async () => {
+    // Upload the 100 config lines in batches of 10 to stay within the transaction size limit
+    for (let index = 0; index < 100; index += 10) {
+      const lines = Array.from({ length: 10 }, (_, i) => ({
+        name: `Test #${index + i + 1}`,
+        uri: `https://arweave.net/${index + i + 1}`,
+      }));
+
+      await program.methods.addConfigLines(index, lines)
+      .accounts({
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+    }
+
+    console.log("\nConfig lines loaded: ", (await program.account.configLines.fetch(configLines)).lineCount);
+  });
+
   it("Add user to allow list", async () => {
-  const tx = await program.methods.addAllowList(allowedOne.publicKey, 88)
+  const tx = await program.methods.addAllowList(allowedOne.publicKey, 88, 5_000)
     .accounts({
       authority: provider.wallet.publicKey,
     })
@@ -187,13 +503,12 @@ describe("cnft-candy-machine", () => {
 
     console.log("\nUser added to allow list: ", allowedOne.publicKey.toBase58());
 
-    const allowList = await program.account.config.fetch(config[0]);
-    console.log("\nAllow list:");
-    allowList.allowList.forEach((user) => console.log("User: ", user.user.toBase58(),"\tAmount: ", user.amount));
+    const entry = await program.account.allowListEntry.fetch(getAllowListEntry(allowedOne.publicKey));
+    console.log("User: ", entry.user.toBase58(), "\tAmount: ", entry.allotted);
   });
 
   it("Add user to allow list", async () => {
-  const tx = await program.methods.addAllowList(allowedTwo.publicKey, 10)
+  const tx = await program.methods.addAllowList(allowedTwo.publicKey, 10, null)
     .accounts({
       authority: provider.wallet.publicKey,
     })
@@ -201,13 +516,12 @@ describe("cnft-candy-machine", () => {
 
     console.log("\nUser added to allow list: ", allowedTwo.publicKey.toBase58());
 
-    const allowList = await program.account.config.fetch(config[0]);
-    console.log("\nAllow list:");
-    allowList.allowList.forEach((user) => console.log("User: ", user.user.toBase58(),"\tAmount: ", user.amount));
+    const entry = await program.account.allowListEntry.fetch(getAllowListEntry(allowedTwo.publicKey));
+    console.log("User: ", entry.user.toBase58(), "\tAmount: ", entry.allotted);
   });
 
   it("Add user to allow list", async () => {
-  const tx = await program.methods.addAllowList(allowedThree.publicKey, 50)
+  const tx = await program.methods.addAllowList(allowedThree.publicKey, 50, null)
     .accounts({
       authority: provider.wallet.publicKey,
     })
@@ -215,21 +529,48 @@ describe("cnft-candy-machine", () => {
 
     console.log("\nUser added to allow list: ", allowedThree.publicKey.toBase58());
 
-    const allowList = await program.account.config.fetch(config[0]);
-    console.log("\nAllow list:");
-    allowList.allowList.forEach((user) => console.log("User: ", user.user.toBase58(),"\tAmount: ", user.amount));
+    const entry = await program.account.allowListEntry.fetch(getAllowListEntry(allowedThree.publicKey));
+    console.log("User: ", entry.user.toBase58(), "\tAmount: ", entry.allotted);
   });
 
   it("Mint cNFT with Allow List - Pay with SOL", async() => {
     console.log("\nMinting cNFT for user: ", allowedOne.publicKey.toBase58());
-    console.log("User allowed amount: ", await program.account.config.fetch(config[0]).then((config) => config.allowList.find((user) => user.user.equals(allowedOne.publicKey))?.amount));
+    console.log("User allowed amount: ", await program.account.allowListEntry.fetch(getAllowListEntry(allowedOne.publicKey)).then((entry) => entry.allotted - entry.claimed));
 
-    const tx = await program.methods.mint("Test", "TST", "https://arweave.net/123", true)
+    const treasuryBefore = await provider.connection.getBalance(treasury);
+
+    const tx = await program.methods.mint(null, null, null, null)
     .accounts({
       user: allowedOne.publicKey,
       authority: provider.wallet.publicKey,
+      configLines,
+      allowListEntry: getAllowListEntry(allowedOne.publicKey),
+      mintCounter: null,
+      gatekeeper: null,
+      gatekeeperNonce: null,
+      instructionsSysvar: null,
+      tokenGateAccount: null,
+      tokenGateReceipt: null,
+      tokenGateMint: null,
+      tokenGateEscrow: null,
+      tokenGateTokenProgram: null,
+      nftGateMint: null,
+      nftGateAccount: null,
+      nftGateMetadata: null,
+      nftGateReceipt: null,
+      cnftGateMerkleTree: null,
+      cnftGateReceipt: null,
       allowMint: null,
       allowMintAta: null,
+      allowMintTokenProgram: null,
+      paymentMint: null,
+      paymentSource: null,
+      paymentDestination: null,
+      paymentTokenProgram: null,
+      associatedTokenProgram: null,
+      auctionReceipt: null,
+      priceOracle: null,
+      goalReceipt: null,
       treeConfig: treeConfigPublicKey,
       merkleTree: emptyMerkleTree.publicKey,
     })
@@ -237,7 +578,175 @@ describe("cnft-candy-machine", () => {
     .rpc();
 
     console.log(`\ncNFT minted for user: ${allowedOne.publicKey.toBase58()} with tx: ${tx}`);
-    console.log("User allowed amount: ", await program.account.config.fetch(config[0]).then((config) => config.allowList.find((user) => user.user.equals(allowedOne.publicKey))?.amount));
+    console.log("User allowed amount: ", await program.account.allowListEntry.fetch(getAllowListEntry(allowedOne.publicKey)).then((entry) => entry.allotted - entry.claimed));
+
+    // The 50% discount of the allow list entry is applied to the 0.2 SOL price
+    assert.equal(await provider.connection.getBalance(treasury) - treasuryBefore, 0.1 * LAMPORTS_PER_SOL);
+  })
+
+  it("Add users to allow list in batch", async () => {
+    const users = Array.from({ length: 5 }, () => ({ user: Keypair.generate().publicKey, amount: 3, discountBps: null }));
+
+    const tx = await program.methods.addAllowListBatch(users)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .remainingAccounts(users.map(({ user }) => ({ pubkey: getAllowListEntry(user), isWritable: true, isSigner: false })))
+    .rpc();
+
+    console.log("\nUsers added to allow list: ", users.length);
+    console.log("Your transaction signature", tx);
+  });
+
+  it("Add a user whose allow list entry already holds lamports in batch", async () => {
+    const user = Keypair.generate().publicKey;
+    const allowListEntry = getAllowListEntry(user);
+
+    // Anyone can send lamports to the entry address before the user is added
+    await sendAndConfirmTransaction(provider.connection, new Transaction().add(
+      anchor.web3.SystemProgram.transfer({ fromPubkey: wallet.publicKey, toPubkey: allowListEntry, lamports: 1_000 })
+    ), [wallet.payer]);
+
+    const tx = await program.methods.addAllowListBatch([{ user, amount: 2, discountBps: null }])
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .remainingAccounts([{ pubkey: allowListEntry, isWritable: true, isSigner: false }])
+    .rpc();
+
+    const entry = await program.account.allowListEntry.fetch(allowListEntry);
+    assert.equal(entry.user.toBase58(), user.toBase58());
+    assert.equal(entry.allotted, 2);
+    console.log("\nUser added to allow list: ", user.toBase58());
+    console.log("Your transaction signature", tx);
+  });
+
+  it("Add user already in allow list (shall fail)", async () => {
+    try {
+      await program.methods.addAllowList(allowedOne.publicKey, 1, null)
+      .accounts({
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+    } catch (error) {
+      console.log("\nError: ", error.error.errorMessage);
+    }
+  });
+
+  it("Update user allowed amount", async () => {
+    const tx = await program.methods.updateAllowList(allowedTwo.publicKey, 5, null)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    const entry = await program.account.allowListEntry.fetch(getAllowListEntry(allowedTwo.publicKey));
+    console.log("\nUser: ", entry.user.toBase58(), "\tAmount: ", entry.allotted);
+    console.log("Your transaction signature", tx);
+  });
+
+  it("Remove user from allow list", async () => {
+    const tx = await program.methods.removeAllowList(allowedThree.publicKey)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    console.log("\nUser removed from allow list: ", allowedThree.publicKey.toBase58());
+    console.log("Your transaction signature", tx);
+  });
+
+  it("Set allow list merkle root", async () => {
+    // Single leaf tree: the root is the leaf itself and the proof is empty
+    const leaf = keccak_256(Buffer.concat([allowedMerkle.publicKey.toBuffer(), Buffer.from([2])]));
+
+    const tx = await program.methods.setAllowListRoot(Array.from(leaf))
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    console.log("\nAllow list merkle root set");
+    console.log("Your transaction signature", tx);
+  });
+
+  it("Mint cNFT with Allow List Proof - Pay with SOL", async() => {
+    console.log("\nMinting cNFT for user: ", allowedMerkle.publicKey.toBase58());
+
+    const allowListEntry = getAllowListEntry(allowedMerkle.publicKey);
+
+    const tx = await program.methods.mint(null, null, { amount: 2, proof: [] }, null)
+    .accounts({
+      ...optionalMintAccounts,
+      user: allowedMerkle.publicKey,
+      authority: provider.wallet.publicKey,
+      configLines,
+      allowListEntry,
+      treeConfig: treeConfigPublicKey,
+      merkleTree: emptyMerkleTree.publicKey,
+    } as any)
+    .signers([allowedMerkle])
+    .rpc();
+
+    const entry = await program.account.allowListEntry.fetch(allowListEntry);
+    console.log(`\ncNFT minted for user: ${allowedMerkle.publicKey.toBase58()} with tx: ${tx}`);
+    console.log("User claimed amount: ", entry.claimed, "of", entry.allotted);
+    assert.isTrue(entry.merkle);
+  })
+
+  it("Refuse to remove a claimed entry of the merkle allow list", async() => {
+    try {
+      await program.methods.removeAllowList(allowedMerkle.publicKey)
+      .accounts({
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+      assert.fail("Removed a claimed allow list entry");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "AllowListEntryClaimed");
+    }
+
+    const entry = await program.account.allowListEntry.fetch(getAllowListEntry(allowedMerkle.publicKey));
+    assert.equal(entry.claimed, 1);
+  })
+
+  it("Keep the allotment of a merkle entry taken over by the authority", async() => {
+    await program.methods.updateAllowList(allowedMerkle.publicKey, 3, null)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    const allowListEntry = getAllowListEntry(allowedMerkle.publicKey);
+
+    await program.methods.mint(null, null, { amount: 2, proof: [] }, null)
+    .accounts({
+      ...optionalMintAccounts,
+      user: allowedMerkle.publicKey,
+      authority: provider.wallet.publicKey,
+      configLines,
+      allowListEntry,
+      treeConfig: treeConfigPublicKey,
+      merkleTree: emptyMerkleTree.publicKey,
+    } as any)
+    .signers([allowedMerkle])
+    .rpc();
+
+    const entry = await program.account.allowListEntry.fetch(allowListEntry);
+    assert.equal(entry.allotted, 3);
+    assert.equal(entry.claimed, 2);
+    assert.isFalse(entry.merkle);
+  })
+
+  it("Remove a claimed entry managed by the authority", async() => {
+    await program.methods.removeAllowList(allowedMerkle.publicKey)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    const entry = await provider.connection.getAccountInfo(getAllowListEntry(allowedMerkle.publicKey));
+    assert.isNull(entry);
   })
 
   it("Mint cNFT with Allow Token - Pay with SOL", async() => {
@@ -247,12 +756,38 @@ describe("cnft-candy-machine", () => {
     console.log("Allow mint balance before mint: ", (await provider.connection.getTokenAccountBalance(allowMintAta)).value.uiAmount);
 
 
-    const tx = await program.methods.mint("Test", "TST", "https://arweave.net/123", true)
+    const tx = await program.methods.mint(null, null, null, null)
     .accounts({
       user: wallet.publicKey,
       authority: provider.wallet.publicKey,
+      configLines,
+      allowListEntry: null,
+      mintCounter: null,
+      gatekeeper: null,
+      gatekeeperNonce: null,
+      instructionsSysvar: null,
+      tokenGateAccount: null,
+      tokenGateReceipt: null,
+      tokenGateMint: null,
+      tokenGateEscrow: null,
+      tokenGateTokenProgram: null,
+      nftGateMint: null,
+      nftGateAccount: null,
+      nftGateMetadata: null,
+      nftGateReceipt: null,
+      cnftGateMerkleTree: null,
+      cnftGateReceipt: null,
       allowMint,
       allowMintAta,
+      allowMintTokenProgram: TOKEN_PROGRAM_ID,
+      paymentMint: null,
+      paymentSource: null,
+      paymentDestination: null,
+      paymentTokenProgram: null,
+      associatedTokenProgram: null,
+      auctionReceipt: null,
+      priceOracle: null,
+      goalReceipt: null,
       treeConfig: treeConfigPublicKey,
       merkleTree: emptyMerkleTree.publicKey,
     })
@@ -261,16 +796,178 @@ describe("cnft-candy-machine", () => {
     console.log("Allow mint balance after mint: ", (await provider.connection.getTokenAccountBalance(allowMintAta)).value.uiAmount);
   })
 
+  it("Apply the allow list entry discount over the gate discounts", async() => {
+    const price = 100_000_000;
+    const hiddenSettings = { name: "Hidden", uri: "https://arweave.net/hidden", hash: Array(32).fill(0) };
+    const machine = await createCandyMachine({ totalSupply: 3, priceSol: new anchor.BN(price), hiddenSettings, allowMint });
+
+    // Allow listed users get 20% off and allow mint burners mint for free, unless their allow list entry has its own discount
+    await program.methods.setGateDiscounts([
+      { gate: { allowList: {} }, discountBps: 2_000 },
+      { gate: { allowMint: {} }, discountBps: 10_000 },
+    ])
+    .accounts({
+      authority: machine.authority.publicKey,
+    })
+    .signers([machine.authority])
+    .rpc();
+
+    for (const [user, discountBps] of [[allowedOne, 5_000], [publicOne, null]] as [Keypair, number | null][]) {
+      await program.methods.addAllowList(user.publicKey, 1, discountBps)
+      .accounts({
+        authority: machine.authority.publicKey,
+      })
+      .signers([machine.authority])
+      .rpc();
+    }
+
+    const allowListEntry = (user: PublicKey) => PublicKey.findProgramAddressSync([Buffer.from("allow"), machine.config.toBuffer(), user.toBuffer()], program.programId)[0];
+    const treasuryPayment = async (mint: () => Promise<string>) => {
+      const balanceBefore = await provider.connection.getBalance(machine.treasury);
+      await mint();
+      return await provider.connection.getBalance(machine.treasury) - balanceBefore;
+    };
+
+    // The entry discount of 50% takes precedence over the 20% allow list gate discount
+    assert.equal(await treasuryPayment(() => mintFrom(machine, allowedOne, { allowListEntry: allowListEntry(allowedOne.publicKey) })), price / 2);
+
+    // Without an entry discount, the gate discount is applied
+    assert.equal(await treasuryPayment(() => mintFrom(machine, publicOne, { allowListEntry: allowListEntry(publicOne.publicKey) })), price * 8 / 10);
+
+    // Burning an allow mint token is free
+    const allowMintAta = getAssociatedTokenAddressSync(allowMint, wallet.publicKey);
+    assert.equal(await treasuryPayment(() => mintFrom(machine, wallet.payer, { allowMint, allowMintAta, allowMintTokenProgram: TOKEN_PROGRAM_ID })), 0);
+  })
+
+  it("Mint cNFT holding the gate token - Pay with SOL", async() => {
+    await program.methods.setTokenGate({ mint: paymentMint, amount: new anchor.BN(1_000_000), singleUse: true })
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    const tokenGateAccount = getAssociatedTokenAddressSync(paymentMint, publicOne.publicKey);
+    const tokenGateReceipt = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("token_gate"), config[0].toBuffer(), publicOne.publicKey.toBuffer()], program.programId)[0];
+    const tokenGateEscrow = getAssociatedTokenAddressSync(paymentMint, tokenGateReceipt, true);
+    const balanceBefore = Number((await provider.connection.getTokenAccountBalance(tokenGateAccount)).value.amount);
+
+    const tx = await mintFrom(mainMachine, publicOne, {
+      configLines,
+      tokenGateAccount,
+      tokenGateReceipt,
+      tokenGateMint: paymentMint,
+      tokenGateEscrow,
+      tokenGateTokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    });
+
+    console.log("\ncNFT minted to token holder with tx: ", tx);
+
+    // The gate tokens are locked in the escrow of the receipt, so they cannot claim again from another wallet
+    assert.equal(Number((await provider.connection.getTokenAccountBalance(tokenGateAccount)).value.amount), balanceBefore - 1_000_000);
+    assert.equal(Number((await provider.connection.getTokenAccountBalance(tokenGateEscrow)).value.amount), 1_000_000);
+
+    const releaseTokenGate = () => program.methods.releaseTokenGate()
+    .accounts({
+      user: publicOne.publicKey,
+      authority: provider.wallet.publicKey,
+      tokenGateMint: paymentMint,
+      userTokenAccount: tokenGateAccount,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([publicOne])
+    .rpc();
+
+    // The tokens stay locked while the sale is running
+    try {
+      await releaseTokenGate();
+      assert.fail("Released the gate tokens during the sale");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "TokenGateLocked");
+    }
+
+    // Once the token gate no longer locks tokens, the user gets them back and the escrow and receipt are closed
+    await program.methods.setTokenGate({ mint: paymentMint, amount: new anchor.BN(1_000_000), singleUse: false })
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    await releaseTokenGate();
+
+    assert.equal(Number((await provider.connection.getTokenAccountBalance(tokenGateAccount)).value.amount), balanceBefore);
+    assert.isNull(await provider.connection.getAccountInfo(tokenGateEscrow));
+    assert.isNull(await provider.connection.getAccountInfo(tokenGateReceipt));
+  })
+
+  it("Mint cNFT holding an NFT of the gate collection - Pay with SOL", async() => {
+    // Create a collection and an NFT of that collection held by the user
+    const metaplex = Metaplex.make(provider.connection).use(keypairIdentity(wallet.payer));
+    const { nft: gateCollection } = await metaplex.nfts().create({ name: "Gate", uri: "https://arweave.net/gate", sellerFeeBasisPoints: 0, isCollection: true });
+    const { nft: gateNft } = await metaplex.nfts().create({
+      name: "Gate #1",
+      uri: "https://arweave.net/gate/1",
+      sellerFeeBasisPoints: 0,
+      collection: gateCollection.address,
+      collectionAuthority: wallet.payer,
+      tokenOwner: publicOne.publicKey,
+    });
+
+    await program.methods.setNftGate({ collection: gateCollection.address, singleUse: true })
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    const nftGateReceipt = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("nft_gate"), config[0].toBuffer(), gateNft.address.toBuffer()], program.programId)[0];
+
+    const tx = await mintFrom(mainMachine, publicOne, {
+      configLines,
+      nftGateMint: gateNft.address,
+      nftGateAccount: getAssociatedTokenAddressSync(gateNft.address, publicOne.publicKey),
+      nftGateMetadata: await getMetadata(gateNft.address),
+      nftGateReceipt,
+    });
+
+    console.log("\ncNFT minted to NFT holder with tx: ", tx);
+  })
+
   it("Mint cNFT to Public User (Tree is Private, so test shall fail) - Pay with SOL", async() => {
     try {
       console.log("\nMinting cNFT for user: ", publicOne.publicKey.toBase58());
 
-      const tx = await program.methods.mint("Test", "TST", "https://arweave.net/123", true)
+      const tx = await program.methods.mint(null, null, null, null)
       .accounts({
         user: publicOne.publicKey,
         authority: provider.wallet.publicKey,
+        configLines,
+        allowListEntry: null,
+        mintCounter: null,
+        gatekeeper: null,
+        gatekeeperNonce: null,
+        instructionsSysvar: null,
+        tokenGateAccount: null,
+        tokenGateReceipt: null,
+        tokenGateMint: null,
+        tokenGateEscrow: null,
+        tokenGateTokenProgram: null,
+        nftGateMint: null,
+        nftGateAccount: null,
+        nftGateMetadata: null,
+        nftGateReceipt: null,
+        cnftGateMerkleTree: null,
+        cnftGateReceipt: null,
         allowMint: null,
         allowMintAta: null,
+        allowMintTokenProgram: null,
+        paymentMint: null,
+        paymentSource: null,
+        paymentDestination: null,
+        paymentTokenProgram: null,
+        associatedTokenProgram: null,
+        auctionReceipt: null,
+        priceOracle: null,
+        goalReceipt: null,
         treeConfig: treeConfigPublicKey,
         merkleTree: emptyMerkleTree.publicKey,
       })
@@ -284,30 +981,106 @@ describe("cnft-candy-machine", () => {
     }
   })
 
-  it("Change Tree Status to Public", async() => {
-    console.log("\nCurrent tree status: ", await program.account.config.fetch(config[0]).then((config) => config.status));
-
-    const tx = await program.methods.setTreeStatus({ public: {} })
+  it("Mint cNFT to Public User with bot tax (Tree is Private, so user is charged the bot tax)", async() => {
+    await program.methods.setBotTax(new anchor.BN(0.01 * LAMPORTS_PER_SOL))
     .accounts({
       authority: provider.wallet.publicKey,
     })
     .rpc();
 
-    console.log("\nTree status changed to Public");
-    console.log("Current tree status: ", await program.account.config.fetch(config[0]).then((config) => config.status));
-    console.log("\nTransaction signature:", tx);
-  })
+    const balanceBefore = await provider.connection.getBalance(publicOne.publicKey);
+    const treasuryBefore = await provider.connection.getBalance(treasury);
+    const supplyBefore = (await program.account.config.fetch(config[0])).currentSupply;
 
-  it("Mint cNFT to Public User (Tree is now public) - Pay with SOL", async() => {
-    console.log("\nMinting cNFT for user: ", publicOne.publicKey.toBase58());
+    const tx = await mintFrom(mainMachine, publicOne, { configLines });
+
+    const balanceAfter = await provider.connection.getBalance(publicOne.publicKey);
+    console.log("\nBot tax charged to Public User: ", (balanceBefore - balanceAfter) / LAMPORTS_PER_SOL);
+    console.log("Transaction signature:", tx);
+
+    // The bot tax is sent to the treasury and nothing is minted
+    assert.equal(balanceBefore - balanceAfter, 0.01 * LAMPORTS_PER_SOL);
+    assert.equal(await provider.connection.getBalance(treasury) - treasuryBefore, 0.01 * LAMPORTS_PER_SOL);
+    assert.equal((await program.account.config.fetch(config[0])).currentSupply, supplyBefore);
 
-    const tx = await program.methods.mint("Test", "TST", "https://arweave.net/123", true)
+    await program.methods.setBotTax(null)
     .accounts({
-      user: publicOne.publicKey,
       authority: provider.wallet.publicKey,
-      allowMint: null,
-      allowMintAta: null,
-      treeConfig: treeConfigPublicKey,
+    })
+    .rpc();
+  })
+
+  it("Mint cNFT before the sale starts (shall fail)", async() => {
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods.setSaleWindow(new anchor.BN(now + 3600), null)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    try {
+      await mintFrom(mainMachine, allowedOne, { configLines, allowListEntry: getAllowListEntry(allowedOne.publicKey) });
+    } catch (error) {
+      console.log("\nError: ", error.error.errorMessage);
+    }
+
+    await program.methods.setSaleWindow(null, null)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+  })
+
+  it("Change Tree Status to Public", async() => {
+    console.log("\nCurrent tree status: ", await program.account.config.fetch(config[0]).then((config) => config.status));
+
+    const tx = await program.methods.setTreeStatus({ public: {} })
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    console.log("\nTree status changed to Public");
+    console.log("Current tree status: ", await program.account.config.fetch(config[0]).then((config) => config.status));
+    console.log("\nTransaction signature:", tx);
+  })
+
+  it("Mint cNFT to Public User (Tree is now public) - Pay with SOL", async() => {
+    console.log("\nMinting cNFT for user: ", publicOne.publicKey.toBase58());
+
+    const tx = await program.methods.mint(null, null, null, null)
+    .accounts({
+      user: publicOne.publicKey,
+      authority: provider.wallet.publicKey,
+      configLines,
+      allowListEntry: null,
+      mintCounter: null,
+      gatekeeper: null,
+      gatekeeperNonce: null,
+      instructionsSysvar: null,
+      tokenGateAccount: null,
+      tokenGateReceipt: null,
+      tokenGateMint: null,
+      tokenGateEscrow: null,
+      tokenGateTokenProgram: null,
+      nftGateMint: null,
+      nftGateAccount: null,
+      nftGateMetadata: null,
+      nftGateReceipt: null,
+      cnftGateMerkleTree: null,
+      cnftGateReceipt: null,
+      allowMint: null,
+      allowMintAta: null,
+      allowMintTokenProgram: null,
+      paymentMint: null,
+      paymentSource: null,
+      paymentDestination: null,
+      paymentTokenProgram: null,
+      associatedTokenProgram: null,
+      auctionReceipt: null,
+      priceOracle: null,
+      goalReceipt: null,
+      treeConfig: treeConfigPublicKey,
       merkleTree: emptyMerkleTree.publicKey,
     })
     .signers([publicOne])
@@ -321,23 +1094,45 @@ describe("cnft-candy-machine", () => {
     console.log("\nMinting cNFT for user: ", publicOne.publicKey.toBase58());
 
     const source = await getOrCreateAssociatedTokenAccount(provider.connection, publicOne, paymentMint, publicOne.publicKey);
-    const destination = await getOrCreateAssociatedTokenAccount(provider.connection, wallet.payer, paymentMint, provider.wallet.publicKey);
+    const destination = getAssociatedTokenAddressSync(paymentMint, treasury, true);
 
     console.log("User Payment Mint balance before mint: ", (await provider.connection.getTokenAccountBalance(source.address)).value.uiAmount);
 
-    const tx = await program.methods.mint("Test", "TST", "https://arweave.net/123", false)
+    const tx = await program.methods.mint(paymentMint, null, null, null)
     .accounts({
       user: publicOne.publicKey,
       authority: provider.wallet.publicKey,
+      configLines,
+      allowListEntry: null,
+      mintCounter: null,
+      gatekeeper: null,
+      gatekeeperNonce: null,
+      instructionsSysvar: null,
+      tokenGateAccount: null,
+      tokenGateReceipt: null,
+      tokenGateMint: null,
+      tokenGateEscrow: null,
+      tokenGateTokenProgram: null,
+      nftGateMint: null,
+      nftGateAccount: null,
+      nftGateMetadata: null,
+      nftGateReceipt: null,
+      cnftGateMerkleTree: null,
+      cnftGateReceipt: null,
       allowMint: null,
       allowMintAta: null,
+      allowMintTokenProgram: null,
+      paymentMint,
+      paymentSource: source.address,
+      paymentDestination: destination,
+      paymentTokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      auctionReceipt: null,
+      priceOracle: null,
+      goalReceipt: null,
       treeConfig: treeConfigPublicKey,
       merkleTree: emptyMerkleTree.publicKey,
     })
-    .remainingAccounts([
-      { pubkey: source.address, isWritable: true, isSigner: false },
-      { pubkey: destination.address, isWritable: true, isSigner: false },
-    ])
     .signers([publicOne])
     .rpc({skipPreflight: true});
 
@@ -345,4 +1140,939 @@ describe("cnft-candy-machine", () => {
     console.log("User Payment Mint balance after mint: ", (await provider.connection.getTokenAccountBalance(source.address)).value.uiAmount);
     console.log("\nTransaction signature:", tx);
   })
+
+  it("Accept a second SPL payment currency", async() => {
+    const communityMint = await createMint(provider.connection, wallet.payer, provider.publicKey, provider.publicKey, 9);
+
+    const tx = await program.methods.setPaymentMints([
+      { mint: paymentMint, price: new anchor.BN(5_000_000) },
+      { mint: communityMint, price: new anchor.BN(1_000_000_000) },
+    ])
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .remainingAccounts([
+      { pubkey: paymentMint, isWritable: false, isSigner: false },
+      { pubkey: communityMint, isWritable: false, isSigner: false },
+    ])
+    .rpc();
+
+    console.log("\nPayment mints set");
+    console.log("Your transaction signature", tx);
+  })
+
+  it("Mint cNFT to Public User until the wallet limit is reached", async() => {
+    await program.methods.setMaxPerWallet(1)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    for (let i = 0; i < 2; i++) {
+      try {
+        const tx = await mintFrom(mainMachine, allowedOne, { configLines, mintCounter: getMintCounter(allowedOne.publicKey) });
+
+        console.log("\ncNFT minted with wallet limit with tx: ", tx);
+      } catch (error) {
+        console.log("\nError: ", error.error.errorMessage);
+      }
+    }
+
+    await program.methods.setMaxPerWallet(null)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+  })
+
+  it("Mint cNFT co-signed by the gatekeeper", async() => {
+    await program.methods.setGatekeeper({ key: gatekeeper.publicKey, mode: { coSigner: {} } })
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    const tx = await program.methods.mint(null, null, null, null)
+    .accounts({
+      ...optionalMintAccounts,
+      user: publicOne.publicKey,
+      authority: provider.wallet.publicKey,
+      configLines,
+      gatekeeper: gatekeeper.publicKey,
+      treeConfig: treeConfigPublicKey,
+      merkleTree: emptyMerkleTree.publicKey,
+    } as any)
+    .signers([publicOne, gatekeeper])
+    .rpc();
+
+    console.log("\ncNFT minted with gatekeeper co-signature with tx: ", tx);
+  })
+
+  it("Mint cNFT with a gatekeeper Ed25519 signature", async() => {
+    await program.methods.setGatekeeper({ key: gatekeeper.publicKey, mode: { ed25519: {} } })
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    // The gatekeeper signs (config, user, expiry, nonce) off-chain
+    const expiry = Buffer.alloc(8);
+    expiry.writeBigInt64LE(BigInt(Math.floor(Date.now() / 1000) + 300));
+    const nonce = Buffer.alloc(8);
+    nonce.writeBigUInt64LE(BigInt(1));
+    const message = Buffer.concat([config[0].toBuffer(), publicOne.publicKey.toBuffer(), expiry, nonce]);
+
+    const gatekeeperNonce = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("gatekeeper"), config[0].toBuffer(), publicOne.publicKey.toBuffer()], program.programId)[0];
+
+    const tx = await program.methods.mint(null, null, null, null)
+    .accounts({
+      ...optionalMintAccounts,
+      user: publicOne.publicKey,
+      authority: provider.wallet.publicKey,
+      configLines,
+      gatekeeperNonce,
+      instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+      treeConfig: treeConfigPublicKey,
+      merkleTree: emptyMerkleTree.publicKey,
+    } as any)
+    .preInstructions([Ed25519Program.createInstructionWithPrivateKey({ privateKey: gatekeeper.secretKey, message })])
+    .signers([publicOne])
+    .rpc();
+
+    console.log("\ncNFT minted with gatekeeper Ed25519 signature with tx: ", tx);
+
+    await program.methods.setGatekeeper(null)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+  })
+
+  it("Refuse a replayed or expired gatekeeper Ed25519 signature", async() => {
+    const hiddenSettings = { name: "Hidden", uri: "https://arweave.net/hidden", hash: Array(32).fill(0) };
+    const machine = await createCandyMachine({ totalSupply: 2, priceSol: new anchor.BN(0.01 * LAMPORTS_PER_SOL), hiddenSettings });
+    await setPublic(machine);
+
+    await program.methods.setGatekeeper({ key: gatekeeper.publicKey, mode: { ed25519: {} } })
+    .accounts({
+      authority: machine.authority.publicKey,
+    })
+    .signers([machine.authority])
+    .rpc();
+
+    const gatekeeperNonce = PublicKey.findProgramAddressSync([Buffer.from("gatekeeper"), machine.config.toBuffer(), publicOne.publicKey.toBuffer()], program.programId)[0];
+
+    // Mint with the gatekeeper signature of (config, user, expiry, nonce), valid for the given number of seconds
+    const mintSigned = (nonce: number, validFor: number) => {
+      const expiryBytes = Buffer.alloc(8);
+      expiryBytes.writeBigInt64LE(BigInt(Math.floor(Date.now() / 1000) + validFor));
+      const nonceBytes = Buffer.alloc(8);
+      nonceBytes.writeBigUInt64LE(BigInt(nonce));
+      const message = Buffer.concat([machine.config.toBuffer(), publicOne.publicKey.toBuffer(), expiryBytes, nonceBytes]);
+
+      return program.methods.mint(null, null, null, null)
+      .accounts({
+        ...optionalMintAccounts,
+        user: publicOne.publicKey,
+        authority: machine.authority.publicKey,
+        gatekeeperNonce,
+        instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        treeConfig: machine.treeConfig,
+        merkleTree: machine.merkleTree,
+      } as any)
+      .preInstructions([Ed25519Program.createInstructionWithPrivateKey({ privateKey: gatekeeper.secretKey, message })])
+      .signers([publicOne])
+      .rpc();
+    };
+
+    await mintSigned(2, 300);
+
+    // A signature with a nonce already used, or a lower one, is rejected
+    for (const nonce of [2, 1]) {
+      try {
+        await mintSigned(nonce, 600);
+        assert.fail("Minted with a replayed gatekeeper signature");
+      } catch (error) {
+        assert.equal(error.error.errorCode.code, "GatekeeperNonceUsed");
+      }
+    }
+
+    // A signature past its expiry is rejected, even with a new nonce
+    try {
+      await mintSigned(3, -3600);
+      assert.fail("Minted with an expired gatekeeper signature");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "GatekeeperSignatureExpired");
+    }
+
+    assert.equal((await program.account.gatekeeperNonce.fetch(gatekeeperNonce)).lastNonce.toNumber(), 2);
+    assert.equal((await program.account.config.fetch(machine.config)).currentSupply, 1);
+  })
+
+  it("Mint cNFT with the payment split between recipients - Pay with SOL", async() => {
+    await program.methods.setRecipients([
+      { address: provider.wallet.publicKey, bps: 7_000 },
+      { address: allowedOne.publicKey, bps: 3_000 },
+    ])
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    const balanceBefore = await provider.connection.getBalance(allowedOne.publicKey);
+    const userBalanceBefore = await provider.connection.getBalance(publicOne.publicKey);
+
+    const tx = await mintFrom(mainMachine, publicOne, { configLines }, null, null, [
+      { pubkey: provider.wallet.publicKey, isWritable: true, isSigner: false },
+      { pubkey: allowedOne.publicKey, isWritable: true, isSigner: false },
+    ]);
+
+    console.log("\ncNFT minted with split payment with tx: ", tx);
+    console.log("Recipient share received: ", (await provider.connection.getBalance(allowedOne.publicKey) - balanceBefore) / LAMPORTS_PER_SOL);
+
+    // The wallet pays the transaction fee, so the price is what the user paid. The first recipient gets 70% of it, rounded down,
+    // and the last recipient the remaining 30%
+    const price = userBalanceBefore - await provider.connection.getBalance(publicOne.publicKey);
+    const firstShare = Math.floor(price * 7_000 / 10_000);
+    assert.equal(await provider.connection.getBalance(allowedOne.publicKey) - balanceBefore, price - firstShare);
+
+    await program.methods.setRecipients([])
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+  })
+
+  it("Mint cNFT with the payment split between recipients - Pay with SPL", async() => {
+    const price = 1_000_000;
+    const hiddenSettings = { name: "Hidden", uri: "https://arweave.net/hidden", hash: Array(32).fill(0) };
+    const machine = await createCandyMachine({ totalSupply: 2, priceSpl: new anchor.BN(price), hiddenSettings, splMint: paymentMint });
+    await setPublic(machine);
+
+    // The recipients have no ATA of the payment mint yet, they are created by the mint
+    const recipientOne = Keypair.generate().publicKey;
+    const recipientTwo = Keypair.generate().publicKey;
+    await program.methods.setRecipients([
+      { address: recipientOne, bps: 7_000 },
+      { address: recipientTwo, bps: 3_000 },
+    ])
+    .accounts({
+      authority: machine.authority.publicKey,
+    })
+    .signers([machine.authority])
+    .rpc();
+
+    const source = getAssociatedTokenAddressSync(paymentMint, publicOne.publicKey);
+    const recipientOneAta = getAssociatedTokenAddressSync(paymentMint, recipientOne);
+    const recipientTwoAta = getAssociatedTokenAddressSync(paymentMint, recipientTwo);
+    const tx = await mintFrom(machine, publicOne, {
+      paymentMint,
+      paymentSource: source,
+      paymentTokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    }, paymentMint, null, [
+      { pubkey: recipientOne, isWritable: false, isSigner: false },
+      { pubkey: recipientOneAta, isWritable: true, isSigner: false },
+      { pubkey: recipientTwo, isWritable: false, isSigner: false },
+      { pubkey: recipientTwoAta, isWritable: true, isSigner: false },
+    ]);
+
+    assert.equal((await provider.connection.getTokenAccountBalance(recipientOneAta)).value.amount, "700000");
+    assert.equal((await provider.connection.getTokenAccountBalance(recipientTwoAta)).value.amount, "300000");
+    console.log("\ncNFT minted with split SPL payment with tx: ", tx);
+  })
+
+  it("Mint cNFT priced in USD - Pay with SOL", async() => {
+    await program.methods.setUsdPrice({
+      priceCents: new anchor.BN(1_500),
+      oracle: priceOracle,
+      maxStaleness: new anchor.BN(10 * 365 * 24 * 3600),
+      maxConfidenceBps: 100,
+    })
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    const balanceBefore = await provider.connection.getBalance(publicOne.publicKey);
+
+    const tx = await mintFrom(mainMachine, publicOne, { configLines, priceOracle });
+
+    const paid = balanceBefore - await provider.connection.getBalance(publicOne.publicKey);
+    console.log("\ncNFT minted for 15 USD with tx: ", tx);
+    console.log("SOL paid: ", paid / LAMPORTS_PER_SOL);
+
+    // One SOL is worth price * 10^expo USD in the oracle account (with a negative exponent), so 15 USD is 1,500 cents * 10^9 / (100 * price * 10^expo) lamports
+    const oracleData = (await provider.connection.getAccountInfo(priceOracle)).data;
+    const expo = oracleData.readInt32LE(20);
+    const oraclePrice = oracleData.readBigInt64LE(208);
+    const expected = BigInt(1_500) * BigInt(LAMPORTS_PER_SOL) * BigInt(10) ** BigInt(-expo) / (BigInt(100) * oraclePrice);
+    assert.equal(paid, Number(expected));
+
+    await program.methods.setUsdPrice(null)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+  })
+
+  it("Mint cNFT above the max price on a bonding curve (shall fail) - Pay with SOL", async() => {
+    await program.methods.setBondingCurve({ linear: { incrementBps: 100 } })
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    try {
+      await mintFrom(mainMachine, publicOne, { configLines }, null, null, [], new anchor.BN(0.2 * LAMPORTS_PER_SOL));
+      assert.fail("Minted above the max price");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "PriceExceedsMax");
+    }
+
+    await program.methods.setBondingCurve(null)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+  })
+
+  it("Mint cNFTs along each bonding curve - Pay with SOL", async() => {
+    const basePrice = 100_000_000;
+    const hiddenSettings = { name: "Hidden", uri: "https://arweave.net/hidden", hash: Array(32).fill(0) };
+
+    // Expected price of each mint, in basis points of the base price
+    const curves = [
+      { bondingCurve: { linear: { incrementBps: 1_000 } }, prices: [10_000, 11_000, 12_000] },
+      { bondingCurve: { exponentialStep: { stepSize: 1, growthBps: 1_000 } }, prices: [10_000, 11_000, 12_100] },
+      { bondingCurve: { piecewise: { points: [{ supply: 1, bps: 15_000 }, { supply: 2, bps: 20_000 }] } }, prices: [10_000, 15_000, 20_000] },
+    ];
+
+    for (const { bondingCurve, prices } of curves) {
+      const machine = await createCandyMachine({ totalSupply: prices.length, priceSol: new anchor.BN(basePrice), hiddenSettings });
+      await setPublic(machine);
+
+      await program.methods.setBondingCurve(bondingCurve as any)
+      .accounts({
+        authority: machine.authority.publicKey,
+      })
+      .signers([machine.authority])
+      .rpc();
+
+      for (const bps of prices) {
+        const balanceBefore = await provider.connection.getBalance(machine.treasury);
+        await mintFrom(machine, publicOne);
+        assert.equal(await provider.connection.getBalance(machine.treasury) - balanceBefore, basePrice * bps / 10_000);
+      }
+    }
+  })
+
+  it("Mint cNFT in a Dutch auction and claim the rebate to the clearing price - Pay with SOL", async() => {
+    const now = Math.floor(Date.now() / 1000);
+
+    await program.methods.setDutchAuction({
+      startTime: new anchor.BN(now - 60),
+      endTime: new anchor.BN(now + 10),
+      floorBps: 5_000,
+      stepInterval: 0,
+      rebate: true,
+    })
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    const balanceBefore = await provider.connection.getBalance(publicOne.publicKey);
+
+    const tx = await mintFrom(mainMachine, publicOne, { configLines, auctionReceipt: getAuctionReceipt(publicOne.publicKey) });
+
+    console.log("\ncNFT minted in Dutch auction with tx: ", tx);
+    console.log("Auction price paid: ", (balanceBefore - await provider.connection.getBalance(publicOne.publicKey)) / LAMPORTS_PER_SOL);
+
+    // Wait for the auction to settle at the floor
+    await new Promise((resolve) => setTimeout(resolve, 12_000));
+
+    const auctionReceipt = getAuctionReceipt(publicOne.publicKey);
+    const receipt = await program.account.auctionReceipt.fetch(auctionReceipt);
+    const receiptRent = (await provider.connection.getAccountInfo(auctionReceipt)).lamports;
+    const balanceBeforeRebate = await provider.connection.getBalance(publicOne.publicKey);
+
+    const rebateTx = await program.methods.claimRebate()
+    .accounts({
+      user: publicOne.publicKey,
+      authority: provider.wallet.publicKey,
+    })
+    .signers([publicOne])
+    .rpc();
+
+    console.log("Rebate claimed with tx: ", rebateTx);
+
+    // The user gets back what they paid above the floor of 50% of the full price (rounded up), and the receipt rent
+    const clearingCost = Math.ceil(receipt.basePaid.toNumber() * 5_000 / 10_000);
+    const rebate = receipt.paid.toNumber() - clearingCost;
+    assert.isAbove(rebate, 0);
+    assert.equal(await provider.connection.getBalance(publicOne.publicKey) - balanceBeforeRebate, rebate + receiptRent);
+
+    await program.methods.setDutchAuction(null)
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+  })
+
+  it("Replace a settled rebate auction and keep its rebates pending", async() => {
+    const hiddenSettings = { name: "Hidden", uri: "https://arweave.net/hidden", hash: Array(32).fill(0) };
+    const machine = await createCandyMachine({ totalSupply: 3, priceSol: new anchor.BN(0.1 * LAMPORTS_PER_SOL), hiddenSettings });
+    await setPublic(machine);
+
+    const setDutchAuction = (rebate: boolean | null) => {
+      const now = Math.floor(Date.now() / 1000);
+      const dutchAuction = rebate === null ? null : { startTime: new anchor.BN(now - 60), endTime: new anchor.BN(now + 10), floorBps: 5_000, stepInterval: 0, rebate };
+
+      return program.methods.setDutchAuction(dutchAuction)
+      .accounts({
+        authority: machine.authority.publicKey,
+      })
+      .signers([machine.authority])
+      .rpc();
+    };
+
+    await setDutchAuction(true);
+
+    const auctionReceipt = (user: PublicKey) => PublicKey.findProgramAddressSync([Buffer.from("auction"), machine.config.toBuffer(), user.toBuffer()], program.programId)[0];
+    for (const user of [publicOne, allowedOne]) {
+      await mintFrom(machine, user, { auctionReceipt: auctionReceipt(user.publicKey) });
+    }
+
+    // Before the auction settles, it cannot be replaced
+    try {
+      await setDutchAuction(null);
+      assert.fail("Replaced a rebate auction before it settled");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "DutchAuctionLocked");
+    }
+
+    // Wait for the auction to settle at the floor
+    await new Promise((resolve) => setTimeout(resolve, 12_000));
+
+    const claimRebate = (user: Keypair) => program.methods.claimRebate()
+    .accounts({
+      user: user.publicKey,
+      authority: machine.authority.publicKey,
+    })
+    .signers([user])
+    .rpc();
+
+    // Only the first user claims before the auction is removed, the rebate of the second one is kept pending
+    await claimRebate(publicOne);
+    await setDutchAuction(null);
+
+    let config = await program.account.config.fetch(machine.config);
+    assert.isNull(config.dutchAuction);
+    assert.equal(config.pendingRebates.receipts, 1);
+    assert.equal(config.pendingRebates.settledBps, 5_000);
+
+    // A new rebate auction cannot start while rebates are pending, and withdraw keeps them in the treasury
+    try {
+      await setDutchAuction(true);
+      assert.fail("Started a rebate auction with pending rebates");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "DutchAuctionLocked");
+    }
+
+    await program.methods.withdraw(null)
+    .accounts({
+      authority: machine.authority.publicKey,
+      destination: machine.authority.publicKey,
+      mint: null,
+      treasuryTokenAccount: null,
+      destinationTokenAccount: null,
+      tokenProgram: null,
+    })
+    .signers([machine.authority])
+    .rpc();
+
+    const rent = await provider.connection.getMinimumBalanceForRentExemption(0);
+    assert.equal(await provider.connection.getBalance(machine.treasury), rent + config.pendingRebates.outstanding.toNumber());
+
+    // The second user claims the pending rebate, after which a new rebate auction can start
+    await claimRebate(allowedOne);
+    config = await program.account.config.fetch(machine.config);
+    assert.isNull(config.pendingRebates);
+    assert.isAtLeast(await provider.connection.getBalance(machine.treasury), rent);
+
+    await setDutchAuction(true);
+  })
+
+  it("Set a public phase with its own price and supply cap", async() => {
+    const now = Math.floor(Date.now() / 1000);
+
+    const tx = await program.methods.setPhases([
+      {
+        startTime: new anchor.BN(now - 60),
+        endTime: new anchor.BN(now + 3600),
+        priceSol: new anchor.BN(0.1 * LAMPORTS_PER_SOL),
+        paymentMints: [],
+        gate: { public: {} },
+        supplyCap: 1,
+      },
+    ])
+    .accounts({
+      authority: provider.wallet.publicKey,
+    })
+    .rpc();
+
+    console.log("\nPhases set");
+    console.log("Your transaction signature", tx);
+  })
+
+  it("Mint cNFT in the public phase until the phase supply cap is reached", async() => {
+    const mintInPhase = () => mintFrom(mainMachine, publicOne, { configLines });
+
+    const tx = await mintInPhase();
+    console.log("\ncNFT minted in public phase with tx: ", tx);
+
+    // The phase has a supply cap of 1, so the second mint fails
+    try {
+      await mintInPhase();
+      assert.fail("Minted past the phase supply cap");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "PhaseSoldOut");
+    }
+
+    assert.equal((await program.account.config.fetch(config[0])).phases[0].minted, 1);
+  })
+
+  it("Withdraw SOL and SPL from the treasury", async() => {
+    console.log("\nTreasury SOL balance before withdraw: ", await provider.connection.getBalance(treasury) / LAMPORTS_PER_SOL);
+
+    const tx = await program.methods.withdraw(null)
+    .accounts({
+      authority: provider.wallet.publicKey,
+      destination: provider.wallet.publicKey,
+      mint: null,
+      treasuryTokenAccount: null,
+      destinationTokenAccount: null,
+      tokenProgram: null,
+    })
+    .rpc();
+
+    console.log("SOL withdrawn with tx: ", tx);
+
+    // The treasury keeps its rent exemption, so refunds and rebates paid from it later never leave it below it
+    assert.equal(await provider.connection.getBalance(treasury), await provider.connection.getMinimumBalanceForRentExemption(0));
+
+    const destination = await getOrCreateAssociatedTokenAccount(provider.connection, wallet.payer, paymentMint, provider.wallet.publicKey);
+
+    const tx2 = await program.methods.withdraw(null)
+    .accounts({
+      authority: provider.wallet.publicKey,
+      destination: null,
+      mint: paymentMint,
+      treasuryTokenAccount: getAssociatedTokenAddressSync(paymentMint, treasury, true),
+      destinationTokenAccount: destination.address,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .rpc();
+
+    console.log("SPL withdrawn with tx: ", tx2);
+    console.log("Authority Payment Mint balance after withdraw: ", (await provider.connection.getTokenAccountBalance(destination.address)).value.uiAmount);
+  })
+
+  it("Refund the buyer and burn the cNFTs when the sale goal is not met by the deadline", async() => {
+    const price = 100_000_000;
+    const hiddenSettings = { name: "Hidden", uri: "https://arweave.net/hidden", hash: Array(32).fill(0) };
+    const machine = await createCandyMachine({ totalSupply: 3, priceSol: new anchor.BN(price), hiddenSettings });
+    await setPublic(machine);
+
+    await program.methods.setSaleGoal({
+      goal: new anchor.BN(1_000 * LAMPORTS_PER_SOL),
+      deadline: new anchor.BN(Math.floor(Date.now() / 1000) + 10),
+    })
+    .accounts({
+      authority: machine.authority.publicKey,
+    })
+    .signers([machine.authority])
+    .rpc();
+
+    const goalReceipt = PublicKey.findProgramAddressSync([Buffer.from("goal"), machine.config.toBuffer(), publicOne.publicKey.toBuffer()], program.programId)[0];
+    for (let i = 0; i < 2; i++) {
+      await mintFrom(machine, publicOne, { goalReceipt });
+    }
+
+    const receipt = await program.account.goalReceipt.fetch(goalReceipt);
+    assert.equal(receipt.paid.toNumber(), 2 * price);
+    assert.equal(receipt.minted, 2);
+
+    // Wait for the deadline to pass without reaching the goal
+    await new Promise((resolve) => setTimeout(resolve, 12_000));
+
+    const metadata = cnftMetadata(machine, hiddenSettings.name, hiddenSettings.uri, true);
+    const leaves = [0, 1].map((index) => cnftLeaf(machine, publicOne.publicKey, index, metadata));
+
+    // Each refund burns the cNFT and pays back its share of the receipt, the last one closes the receipt
+    for (let index = 0; index < 2; index++) {
+      const balanceBefore = await provider.connection.getBalance(publicOne.publicKey);
+      const tx = await refund(machine, publicOne, metadata, leaves, index);
+      console.log(`\nRefund of cNFT ${index} claimed with tx: ${tx}`);
+
+      leaves[index] = Buffer.alloc(32);
+      assert.isAtLeast(await provider.connection.getBalance(publicOne.publicKey) - balanceBefore, price);
+    }
+
+    assert.isNull(await provider.connection.getAccountInfo(goalReceipt));
+
+    // The cNFTs are burned, and the treasury keeps its rent exemption
+    const root = (await ConcurrentMerkleTreeAccount.fromAccountAddress(provider.connection, machine.merkleTree)).getCurrentRoot();
+    assert.deepEqual(Array.from(root), Array.from(MerkleTree.sparseMerkleTreeFromLeaves(leaves, smallDepthSizePair.maxDepth).root));
+    assert.equal(await provider.connection.getBalance(machine.treasury), await provider.connection.getMinimumBalanceForRentExemption(0));
+    assert.equal((await program.account.config.fetch(machine.config)).saleGoal.refunded.toNumber(), 2 * price);
+  })
+
+  it("Withdraw the treasury SOL not owed to buyers once the sale goal has failed", async() => {
+    const price = 100_000_000;
+    const hiddenSettings = { name: "Hidden", uri: "https://arweave.net/hidden", hash: Array(32).fill(0) };
+    const machine = await createCandyMachine({ totalSupply: 3, priceSol: new anchor.BN(price), hiddenSettings });
+    await setPublic(machine);
+
+    await program.methods.setSaleGoal({
+      goal: new anchor.BN(1_000 * LAMPORTS_PER_SOL),
+      deadline: new anchor.BN(Math.floor(Date.now() / 1000) + 10),
+    })
+    .accounts({
+      authority: machine.authority.publicKey,
+    })
+    .signers([machine.authority])
+    .rpc();
+
+    const goalReceipt = (user: PublicKey) => PublicKey.findProgramAddressSync([Buffer.from("goal"), machine.config.toBuffer(), user.toBuffer()], program.programId)[0];
+    for (const user of [publicOne, allowedOne]) {
+      await mintFrom(machine, user, { goalReceipt: goalReceipt(user.publicKey) });
+    }
+
+    // SOL sent to the treasury on top of the sale payments, which the authority can take back once the goal fails
+    const surplus = 50_000_000;
+    await sendAndConfirmTransaction(provider.connection, new Transaction().add(
+      anchor.web3.SystemProgram.transfer({ fromPubkey: wallet.publicKey, toPubkey: machine.treasury, lamports: surplus })
+    ), [wallet.payer]);
+
+    const withdraw = () => program.methods.withdraw(null)
+    .accounts({
+      authority: machine.authority.publicKey,
+      destination: machine.authority.publicKey,
+      mint: null,
+      treasuryTokenAccount: null,
+      destinationTokenAccount: null,
+      tokenProgram: null,
+    })
+    .signers([machine.authority])
+    .rpc();
+
+    // Before the deadline, the payments are in escrow
+    try {
+      await withdraw();
+      assert.fail("Withdrew before the sale goal was met");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "SaleGoalNotMet");
+    }
+
+    // Wait for the deadline to pass without reaching the goal
+    await new Promise((resolve) => setTimeout(resolve, 12_000));
+
+    // Each user minted one cNFT, publicOne the first one
+    const metadata = cnftMetadata(machine, hiddenSettings.name, hiddenSettings.uri, true);
+    const leaves = [publicOne, allowedOne].map((user, index) => cnftLeaf(machine, user.publicKey, index, metadata));
+    await refund(machine, publicOne, metadata, leaves, 0);
+
+    // The payment of the buyer not refunded yet stays in the treasury, on top of its rent exemption
+    const rent = await provider.connection.getMinimumBalanceForRentExemption(0);
+    const authorityBefore = await provider.connection.getBalance(machine.authority.publicKey);
+    await withdraw();
+    assert.equal(await provider.connection.getBalance(machine.treasury), rent + price);
+    assert.equal(await provider.connection.getBalance(machine.authority.publicKey) - authorityBefore, surplus);
+
+    const saleGoal = (await program.account.config.fetch(machine.config)).saleGoal;
+    assert.equal(saleGoal.refunded.toNumber(), price);
+    assert.equal(saleGoal.raised.toNumber(), 2 * price);
+  })
+
+  it("Mint hidden cNFTs and reveal them with the config lines", async() => {
+    const hiddenSettings = { name: "Hidden", uri: "https://arweave.net/hidden", hash: Array(32).fill(0) };
+    const machine = await createCandyMachine({ totalSupply: 2, priceSol: new anchor.BN(0.1 * LAMPORTS_PER_SOL), hiddenSettings });
+    hiddenMachine = machine;
+    await setPublic(machine);
+
+    // Every cNFT is minted with the placeholder, without config lines
+    for (let i = 0; i < 2; i++) {
+      await mintFrom(machine, publicOne);
+    }
+    console.log("\nHidden cNFTs minted");
+
+    await loadConfigLines(machine, false, 2);
+
+    // Once minting has started, loaded lines cannot be rewritten
+    try {
+      await program.methods.addConfigLines(0, [{ name: "Other", uri: "https://arweave.net/other" }])
+      .accounts({
+        authority: machine.authority.publicKey,
+      })
+      .signers([machine.authority])
+      .rpc();
+      assert.fail("Rewrote a loaded config line after minting");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "ConfigLinesLocked");
+    }
+
+    const leaves = [0, 1].map((index) => cnftLeaf(machine, publicOne.publicKey, index, cnftMetadata(machine, hiddenSettings.name, hiddenSettings.uri, true)));
+
+    for (let index = 0; index < 2; index++) {
+      const { proof, root } = await leafProof(machine, leaves, index);
+
+      const tx = await program.methods.reveal(root, new anchor.BN(index), index)
+      .accounts({
+        authority: machine.authority.publicKey,
+        leafOwner: publicOne.publicKey,
+        leafDelegate: publicOne.publicKey,
+        treeConfig: machine.treeConfig,
+        merkleTree: machine.merkleTree,
+      })
+      .remainingAccounts(proof)
+      .signers([machine.authority])
+      .rpc();
+
+      // The revealed leaf now holds the metadata of its config line
+      leaves[index] = cnftLeaf(machine, publicOne.publicKey, index, cnftMetadata(machine, `Test #${index + 1}`, `https://arweave.net/${index + 1}`, true));
+      console.log(`cNFT ${index} revealed with tx: ${tx}`);
+    }
+
+    const root = (await ConcurrentMerkleTreeAccount.fromAccountAddress(provider.connection, machine.merkleTree)).getCurrentRoot();
+    assert.deepEqual(Array.from(root), Array.from(MerkleTree.sparseMerkleTreeFromLeaves(leaves, smallDepthSizePair.maxDepth).root));
+  })
+
+  it("Mint every config line of a random candy machine once", async() => {
+    const machine = await createCandyMachine({ totalSupply: 3, priceSol: new anchor.BN(0.1 * LAMPORTS_PER_SOL) });
+    await setPublic(machine);
+    await loadConfigLines(machine, true, 3);
+
+    for (let i = 0; i < 3; i++) {
+      await mintFrom(machine, publicOne, { configLines: machine.configLines });
+    }
+    console.log("\nRandom cNFTs minted");
+
+    // The lines are drawn without replacement, so the tree holds the three lines in one of their orders
+    const permutations = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+    const root = Array.from((await ConcurrentMerkleTreeAccount.fromAccountAddress(provider.connection, machine.merkleTree)).getCurrentRoot());
+    const drawn = permutations.find((order) => {
+      const leaves = order.map((line, index) => cnftLeaf(machine, publicOne.publicKey, index, cnftMetadata(machine, `Test #${line + 1}`, `https://arweave.net/${line + 1}`, false)));
+      return Buffer.from(MerkleTree.sparseMerkleTreeFromLeaves(leaves, smallDepthSizePair.maxDepth).root).equals(Buffer.from(root));
+    });
+    assert.isDefined(drawn);
+    console.log("Config lines drawn in order", drawn);
+
+    // Once every line has been drawn, the next mint is refused instead of drawing from an empty set
+    try {
+      await mintFrom(machine, publicOne, { configLines: machine.configLines });
+      assert.fail("Minted past the total supply");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "SoldOut");
+    }
+  })
+
+  it("Mint cNFT by proving a cNFT of the gate tree is held", async() => {
+    // The gate cNFT is minted by a first candy machine, the second one is gated by its tree
+    const hiddenSettings = { name: "Gate", uri: "https://arweave.net/gate", hash: Array(32).fill(0) };
+    const source = await createCandyMachine({ totalSupply: 1, priceSol: new anchor.BN(0.1 * LAMPORTS_PER_SOL), hiddenSettings });
+    await setPublic(source);
+    await mintFrom(source, publicOne);
+
+    const gated = await createCandyMachine({ totalSupply: 2, priceSol: new anchor.BN(0.1 * LAMPORTS_PER_SOL), hiddenSettings });
+    await program.methods.setCnftGate({ merkleTree: source.merkleTree, singleUse: true })
+    .accounts({
+      authority: gated.authority.publicKey,
+    })
+    .signers([gated.authority])
+    .rpc();
+
+    const metadata = cnftMetadata(source, hiddenSettings.name, hiddenSettings.uri, true);
+    const { proof, root } = await leafProof(source, [cnftLeaf(source, publicOne.publicKey, 0, metadata)], 0);
+    const cnftGateProof = {
+      root,
+      dataHash: Array.from(hashMetadataData(metadata)),
+      creatorHash: Array.from(hashMetadataCreators(metadata.creators)),
+      nonce: new anchor.BN(0),
+      index: 0,
+      delegate: null,
+    };
+
+    const assetId = new PublicKey(findLeafAssetIdPda(umi, { merkleTree: publicKey(source.merkleTree.toBase58()), leafIndex: 0 })[0]);
+    const cnftGateReceipt = PublicKey.findProgramAddressSync([Buffer.from("cnft_gate"), gated.config.toBuffer(), assetId.toBuffer()], program.programId)[0];
+    const gateAccounts = { cnftGateMerkleTree: source.merkleTree, cnftGateReceipt };
+
+    const tx = await mintFrom(gated, publicOne, gateAccounts, null, cnftGateProof, proof);
+    const receipt = await program.account.gateReceipt.fetch(cnftGateReceipt);
+    assert.isTrue(receipt.used);
+    console.log("\ncNFT minted with the cNFT gate with tx: ", tx);
+
+    // With single use, the same cNFT cannot unlock a second mint
+    try {
+      await mintFrom(gated, publicOne, gateAccounts, null, cnftGateProof, proof);
+      assert.fail("Minted twice with a single use cNFT");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "CnftGateAlreadyUsed");
+    }
+
+    // The receipt is derived from the nonce, so a nonce that is not the leaf index is refused
+    const otherAssetId = new PublicKey(findLeafAssetIdPda(umi, { merkleTree: publicKey(source.merkleTree.toBase58()), leafIndex: 1 })[0]);
+    const otherReceipt = PublicKey.findProgramAddressSync([Buffer.from("cnft_gate"), gated.config.toBuffer(), otherAssetId.toBuffer()], program.programId)[0];
+    try {
+      await mintFrom(gated, publicOne, { ...gateAccounts, cnftGateReceipt: otherReceipt }, null, { ...cnftGateProof, nonce: new anchor.BN(1) }, proof);
+      assert.fail("Minted with a nonce that is not the leaf index");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidCnftGateProof");
+    }
+  })
+
+  it("Mint cNFT by burning a Token-2022 Allow Mint token", async() => {
+    const allowMint2022 = await createMint(provider.connection, wallet.payer, wallet.publicKey, null, 6, Keypair.generate(), undefined, TOKEN_2022_PROGRAM_ID);
+    const allowMintAta = (await getOrCreateAssociatedTokenAccount(provider.connection, wallet.payer, allowMint2022, publicOne.publicKey, false, undefined, undefined, TOKEN_2022_PROGRAM_ID)).address;
+    await mintTo(provider.connection, wallet.payer, allowMint2022, allowMintAta, wallet.payer, 2_000_000, [], undefined, TOKEN_2022_PROGRAM_ID);
+
+    // The candy machine stays private, so the Allow Mint is the only way to mint
+    const hiddenSettings = { name: "Hidden", uri: "https://arweave.net/hidden", hash: Array(32).fill(0) };
+    const machine = await createCandyMachine({ totalSupply: 2, priceSol: new anchor.BN(0.1 * LAMPORTS_PER_SOL), hiddenSettings, allowMint: allowMint2022 });
+
+    const tx = await mintFrom(machine, publicOne, {
+      allowMint: allowMint2022,
+      allowMintAta,
+      allowMintTokenProgram: TOKEN_2022_PROGRAM_ID,
+    });
+
+    // One whole token is burned by default
+    const balance = await provider.connection.getTokenAccountBalance(allowMintAta);
+    assert.equal(balance.value.amount, "1000000");
+    console.log("\ncNFT minted by burning a Token-2022 Allow Mint token with tx: ", tx);
+  })
+
+  it("Mint cNFT paying with a transfer fee mint - the treasury receives the price", async() => {
+    // 1% transfer fee, withheld from every transfer
+    const feeMint = await createMint2022(ExtensionType.TransferFeeConfig, (mint) =>
+      createInitializeTransferFeeConfigInstruction(mint, wallet.publicKey, wallet.publicKey, 100, BigInt(1_000_000_000), TOKEN_2022_PROGRAM_ID)
+    );
+    const source = (await getOrCreateAssociatedTokenAccount(provider.connection, wallet.payer, feeMint, publicOne.publicKey, false, undefined, undefined, TOKEN_2022_PROGRAM_ID)).address;
+    await mintTo(provider.connection, wallet.payer, feeMint, source, wallet.payer, 10_000_000, [], undefined, TOKEN_2022_PROGRAM_ID);
+
+    const price = 1_000_000;
+    const hiddenSettings = { name: "Hidden", uri: "https://arweave.net/hidden", hash: Array(32).fill(0) };
+    const machine = await createCandyMachine({ totalSupply: 2, priceSpl: new anchor.BN(price), hiddenSettings, splMint: feeMint });
+    await setPublic(machine);
+
+    const destination = getAssociatedTokenAddressSync(feeMint, machine.treasury, true, TOKEN_2022_PROGRAM_ID);
+    const paymentAccounts = {
+      paymentMint: feeMint,
+      paymentSource: source,
+      paymentDestination: destination,
+      paymentTokenProgram: TOKEN_2022_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    };
+
+    // The max price is compared to the amount debited from the user, so accepting only the price fails once the fee is added
+    try {
+      await mintFrom(machine, publicOne, paymentAccounts, feeMint, null, [], new anchor.BN(price));
+      assert.fail("Minted above the max price");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "PriceExceedsMax");
+    }
+
+    const tx = await mintFrom(machine, publicOne, paymentAccounts, feeMint);
+
+    // The user pays the price grossed up by the fee, and the treasury receives exactly the price
+    const balance = await provider.connection.getTokenAccountBalance(destination);
+    assert.equal(balance.value.amount, price.toString());
+    console.log("\ncNFT minted with a transfer fee mint with tx: ", tx);
+  })
+
+  it("Refuse a payment mint with an unsupported extension", async() => {
+    const nonTransferableMint = await createMint2022(ExtensionType.NonTransferable, (mint) =>
+      createInitializeNonTransferableMintInstruction(mint, TOKEN_2022_PROGRAM_ID)
+    );
+
+    try {
+      await program.methods.setPaymentMints([{ mint: nonTransferableMint, price: new anchor.BN(1_000_000) }])
+      .accounts({
+        authority: provider.wallet.publicKey,
+      })
+      .remainingAccounts([{ pubkey: nonTransferableMint, isWritable: false, isSigner: false }])
+      .rpc();
+      assert.fail("Accepted a non-transferable payment mint");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "UnsupportedPaymentMint");
+    }
+  })
+
+  it("Refuse a permanent delegate payment mint", async() => {
+    const permanentDelegateMint = await createMint2022(ExtensionType.PermanentDelegate, (mint) =>
+      createInitializePermanentDelegateInstruction(mint, provider.wallet.publicKey, TOKEN_2022_PROGRAM_ID)
+    );
+
+    try {
+      await program.methods.setPaymentMints([{ mint: permanentDelegateMint, price: new anchor.BN(1_000_000) }])
+      .accounts({
+        authority: provider.wallet.publicKey,
+      })
+      .remainingAccounts([{ pubkey: permanentDelegateMint, isWritable: false, isSigner: false }])
+      .rpc();
+      assert.fail("Accepted a permanent delegate payment mint");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "UnsupportedPaymentMint");
+    }
+  })
+
+  it("Refuse to mint past the total supply of a hidden settings candy machine", async() => {
+    // The hidden settings machine was fully minted by the reveal test, and its config is never closed
+    try {
+      await mintFrom(hiddenMachine, publicOne);
+      assert.fail("Minted past the total supply");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "SoldOut");
+    }
+
+    const machineConfig = await program.account.config.fetch(hiddenMachine.config);
+    assert.equal(machineConfig.currentSupply, machineConfig.totalSupply);
+  })
+
+  it("Close a sold out candy machine and recover its rent and treasury", async() => {
+    const price = 100_000_000;
+    const machine = await createCandyMachine({ totalSupply: 1, priceSol: new anchor.BN(price) });
+    await setPublic(machine);
+    await loadConfigLines(machine, false, 1);
+
+    const close = () => program.methods.close()
+    .accounts({
+      authority: machine.authority.publicKey,
+      configLines: machine.configLines,
+    })
+    .signers([machine.authority])
+    .rpc();
+
+    // The candy machine cannot be closed while cNFTs are left
+    try {
+      await close();
+      assert.fail("Closed a candy machine that is not sold out");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "NotSoldOut");
+    }
+
+    await mintFrom(machine, publicOne, { configLines: machine.configLines });
+
+    // The authority gets back the rent of the config and config lines, and the whole treasury
+    const accounts = [machine.config, machine.configLines, machine.treasury];
+    const recovered = (await Promise.all(accounts.map((account) => provider.connection.getBalance(account)))).reduce((sum, lamports) => sum + lamports, 0);
+    const authorityBefore = await provider.connection.getBalance(machine.authority.publicKey);
+    await close();
+    assert.equal(await provider.connection.getBalance(machine.authority.publicKey) - authorityBefore, recovered);
+
+    for (const account of accounts) {
+      assert.isNull(await provider.connection.getAccountInfo(account));
+    }
+  })
 });